@@ -0,0 +1,62 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+//! Procedural macros for [`procss`].  These are in their own crate (as
+//! procedural macros must be), so `procss` cannot re-export them;  depend on
+//! both `procss` and `procss-macros` to use them.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+/// Parse a CSS+ string literal to a [`procss::ast::Tree`], checking its
+/// syntax at compile time.  A literal which [`procss::parse`] would reject is
+/// a compile error, reported with the parser's message and the line and
+/// column of the error within the literal.
+///
+/// # Example
+///
+/// ```
+/// use procss::RenderCss;
+/// use procss_macros::css;
+///
+/// let tree = css! {"
+///     div {
+///         .open { color: red; }
+///     }
+/// "};
+///
+/// let css = tree.flatten_tree().as_css_string();
+/// assert_eq!(css, "div .open{color:red;}");
+/// ```
+///
+/// ```compile_fail
+/// let tree = procss_macros::css!("div { color: red");
+/// ```
+#[proc_macro]
+pub fn css(input: TokenStream) -> TokenStream {
+    let lit = parse_macro_input!(input as LitStr);
+    if let Err(err) = procss::parse(&lit.value()) {
+        let message = match err.downcast::<procss::ParseError>() {
+            Ok(err) => format!(
+                "{} (at {}:{})",
+                err.message, err.position.line, err.position.column
+            ),
+            Err(err) => err.to_string(),
+        };
+
+        return syn::Error::new(lit.span(), message)
+            .to_compile_error()
+            .into();
+    }
+
+    quote!(::procss::parse_unchecked(#lit).unwrap()).into()
+}
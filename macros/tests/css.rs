@@ -0,0 +1,32 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use procss::RenderCss;
+use procss_macros::css;
+
+#[test]
+fn test_css_macro() {
+    let tree = css!("div { .open { color: red; } }");
+    assert_eq!(tree.flatten_tree().as_css_string(), "div .open{color:red;}");
+}
+
+#[test]
+fn test_css_macro_raw_string() {
+    let tree = css! {r#"
+        @import "x.css";
+        .sm\:flex::after { content: '\'' }
+    "#};
+
+    assert_eq!(
+        tree.as_css_string(),
+        "@import\"x.css\";.sm\\:flex::after{content:'\\'';}"
+    );
+}
@@ -13,33 +13,85 @@
 //! are part of the [`crate::parse`] API, and their various components structs
 //! are necessary for writing any transformations on these between parsing and
 //! rendering.
+//!
+//! # Serialization
+//!
+//! With the `serde` feature enabled, [`Tree`], [`Css`], [`Cst`] and the nodes
+//! they are made of implement `serde::Serialize` and `serde::Deserialize`.
+//! The JSON shape mirrors the structs in this module, per `serde`'s default
+//! representation:
+//!
+//! - Structs with named fields (e.g. [`Rule`], [`SelectorTerm`]) are objects
+//!   keyed by field name.
+//! - Tuple structs (e.g. [`SelectorRuleset`], [`QualRule`]) are arrays of their
+//!   fields, in order.
+//! - Enum variants with data are objects with a single key, the variant name,
+//!   e.g. `{"Rule": {...}}`.  Unit variants (e.g. [`Combinator`]) are strings,
+//!   e.g. `"Desc"`.
+//! - A [`Selector`] is an array of its [`SelectorPath`] alternatives.
+//! - A [`Span`] is `{"start": <position>, "end": <position>}`, where a
+//!   [`Position`] is `{"offset": 0, "line": 1, "column": 1}`.
+//!
+//! For example, the declaration `color: red` in a [`Tree`] is serialized as:
+//!
+//! ```json
+//! {"Rule": {"property": "color", "value": "red", "important": false, "span": {...}}}
+//! ```
+//!
+//! Views which are parsed on demand from these nodes, such as
+//! [`ComponentValue`], [`AtRule`] and [`MediaQuery`], are not serialized.  The
+//! `procss` CLI prints the [`Tree`] of a file as JSON with the `--json` flag.
 
+mod cst;
 mod flat_ruleset;
+mod recover;
 mod ruleset;
 mod selector;
+mod span;
 mod token;
 mod tree_ruleset;
+mod value;
 
 use std::cmp::Ordering;
 
 use winnow::{
-    combinator::{alt, eof, repeat, terminated},
-    error::ParserError,
+    combinator::{cut_err, eof, opt, repeat, terminated},
     unpeek, IResult, Parser,
 };
 
+pub(crate) use self::span::LineIndex;
 pub use self::{
-    flat_ruleset::FlatRuleset,
-    ruleset::{QualNestedRuleset, QualRule, QualRuleset, Rule, Ruleset, SelectorRuleset},
-    selector::{Combinator, Selector, SelectorAttr, SelectorPath, SelectorTerm},
+    cst::{Cst, CstBlock, CstKind, CstNode, CstPrelude},
+    flat_ruleset::{FlatRule, FlatRuleset},
+    ruleset::{
+        AtRule, Comment, Import, Keyframe, KeyframeSelector, KeyframesRuleset, MediaCondition,
+        MediaFeature, MediaModifier, MediaQuery, MediaRangeOp, PageMargin, PageSelector,
+        QualNestedRuleset, QualRule, QualRuleset, Rule, Ruleset, SelectorRuleset,
+        SupportsCondition,
+    },
+    selector::{
+        AttrFlag, AttrOperator, Combinator, Nth, Pseudo, PseudoArg, PseudoMode, Qualifier,
+        Selector, SelectorAttr, SelectorPath, SelectorTerm,
+    },
+    span::{Position, Span},
+    token::Ident,
     tree_ruleset::{TreeRule, TreeRuleset},
+    value::ComponentValue,
+};
+use crate::{
+    owned::IntoOwned,
+    parser::*,
+    render::*,
+    transform::*,
+    transformers,
+    visit::{Context, Visit, VisitMut},
 };
-use crate::{parser::*, render::*, transform::*, transformers};
 
 /// A non-nested "flat" CSS representation, suitable for browser output. The
 /// [`Css`] AST is typically generated via the
 /// [`crate::ast::Tree::flatten_tree`] method.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Css<'a>(pub Vec<FlatRuleset<'a>>);
 
 impl<'a> Css<'a> {
@@ -64,15 +116,58 @@ impl<'a> Css<'a> {
         self.transform_each(&mut f)
     }
 
+    /// Walk this AST with a read-only [`Visit`] visitor, which reaches every
+    /// node and may stop early (see [`crate::visit`]).  Call
+    /// [`Visit::visit_css`] directly to tell whether the walk was stopped.
+    pub fn visit<'n>(&'n self, visitor: &mut impl Visit<'n>) {
+        let _ = visitor.visit_css(self, &mut Context::default());
+    }
+
+    /// Walk this AST with a mutable [`VisitMut`] visitor, which reaches every
+    /// node and may stop early (see [`crate::visit`]).  Call
+    /// [`VisitMut::visit_css_mut`] directly to tell whether the walk was
+    /// stopped.
+    pub fn visit_mut(&mut self, visitor: &mut impl VisitMut<'a>) {
+        let _ = visitor.visit_css_mut(self, &mut Context::default());
+    }
+
     /// Iterate over the immediate children of this Tree (non-recursive).
     pub fn iter(&self) -> impl Iterator<Item = &'_ FlatRuleset<'a>> {
         self.0.iter()
     }
+
+    /// The URL of the namespace `prefix` of a [`SelectorTerm::namespace`], as
+    /// declared by a `@namespace` at-rule.  `""` is the default namespace.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use procss::parse;
+    /// let css = parse("@namespace svg url(http://www.w3.org/2000/svg);svg|a{color:red}");
+    /// let css = css.unwrap().flatten_tree();
+    /// assert_eq!(css.namespace("svg"), Some("http://www.w3.org/2000/svg"));
+    /// ```
+    pub fn namespace(&self, prefix: &str) -> Option<&str> {
+        self.0.iter().find_map(|x| match x {
+            Ruleset::QualRule(rule) => match rule.namespace()? {
+                (Some(x), url) if x == prefix => Some(url),
+                (None, url) if prefix.is_empty() => Some(url),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+}
+
+impl<'a, R: Into<FlatRuleset<'a>>> FromIterator<R> for Css<'a> {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        Css(iter.into_iter().map(Into::into).collect())
+    }
 }
 
 impl<'a, T> TransformCss<T> for Css<'a>
 where
-    Ruleset<'a, Rule<'a>>: TransformCss<T>,
+    FlatRuleset<'a>: TransformCss<T>,
 {
     fn transform_each<F: FnMut(&mut T)>(&mut self, f: &mut F) {
         for rule in self.0.iter_mut() {
@@ -81,6 +176,14 @@ where
     }
 }
 
+impl<'a> IntoOwned for Css<'a> {
+    type Owned = Css<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        Css(self.0.into_owned())
+    }
+}
+
 impl<'a> RenderCss for Css<'a> {
     fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for x in self.0.iter() {
@@ -96,11 +199,15 @@ impl<'a> RenderCss for Css<'a> {
 /// browsers and is not identical to the input since whitespace has been
 /// discarded.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tree<'a>(pub Vec<TreeRuleset<'a>>);
 
 impl<'a> Tree<'a> {
     /// Flatten a nested [`Tree`] into a [`Css`], or in other words, convert the
-    /// result of a parse into something that can be rendered.
+    /// result of a parse into something that can be rendered.  Nested rulesets
+    /// whose selector concatenates a suffix to a `&` which can't take one
+    /// (see [`Selector::join`]) are dropped, as browsers drop rules with
+    /// invalid selectors; [`crate::parse`] reports these as errors instead.
     pub fn flatten_tree(&self) -> Css<'a> {
         let mut rules = self
             .0
@@ -142,10 +249,45 @@ impl<'a> Tree<'a> {
         self.transform_each(&mut f)
     }
 
+    /// Walk this AST with a read-only [`Visit`] visitor, which reaches every
+    /// node and may stop early (see [`crate::visit`]).  Call
+    /// [`Visit::visit_tree`] directly to tell whether the walk was stopped.
+    pub fn visit<'n>(&'n self, visitor: &mut impl Visit<'n>) {
+        let _ = visitor.visit_tree(self, &mut Context::default());
+    }
+
+    /// Walk this AST with a mutable [`VisitMut`] visitor, which reaches every
+    /// node and may stop early (see [`crate::visit`]).  Call
+    /// [`VisitMut::visit_tree_mut`] directly to tell whether the walk was
+    /// stopped.
+    pub fn visit_mut(&mut self, visitor: &mut impl VisitMut<'a>) {
+        let _ = visitor.visit_tree_mut(self, &mut Context::default());
+    }
+
     /// Iterate over the immediate children of this Tree (non-recursive).
     pub fn iter(&self) -> impl Iterator<Item = &'_ TreeRuleset<'a>> {
         self.0.iter()
     }
+
+    /// Parse CSS text to a [`Tree`], skipping invalid declarations and
+    /// rulesets rather than failing on the first error, returning every
+    /// [`ParseError`] encountered along the way.  A declaration is skipped to
+    /// the next `;` or the end of its block, and a ruleset or at-rule is
+    /// skipped to the end of its block.
+    pub fn parse_recover(input: &'a str) -> (Self, Vec<ParseError>) {
+        let body = input.strip_prefix('\u{feff}').unwrap_or(input);
+        let mut recover = recover::Recover::new(body);
+        let mut tree = Tree(recover.tree());
+        let index = LineIndex::new(input);
+        tree.transform(|span: &mut Span| span.resolve(&index));
+        (tree, recover.into_errors())
+    }
+}
+
+impl<'a, R: Into<TreeRuleset<'a>>> FromIterator<R> for Tree<'a> {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        Tree(iter.into_iter().map(Into::into).collect())
+    }
 }
 
 impl<'a> TransformCss<Rule<'a>> for Tree<'a> {
@@ -156,6 +298,14 @@ impl<'a> TransformCss<Rule<'a>> for Tree<'a> {
     }
 }
 
+impl<'a> TransformCss<Comment<'a>> for Tree<'a> {
+    fn transform_each<F: FnMut(&mut Comment<'a>)>(&mut self, f: &mut F) {
+        for rule in self.0.iter_mut() {
+            rule.transform_each(f);
+        }
+    }
+}
+
 impl<'a> TransformCss<TreeRuleset<'a>> for Tree<'a> {
     fn transform_each<F: FnMut(&mut TreeRuleset<'a>)>(&mut self, f: &mut F) {
         for rule in self.0.iter_mut() {
@@ -168,6 +318,8 @@ impl<'a> TransformCss<TreeRuleset<'a>> for Tree<'a> {
                 }
                 Ruleset::QualRule(_) => (),
                 Ruleset::QualRuleset(_) => (),
+                Ruleset::Keyframes(_) => (),
+                Ruleset::Comment(_) => (),
                 Ruleset::QualNestedRuleset(ruleset) => {
                     for rule in ruleset.1.iter_mut() {
                         rule.transform_each(f)
@@ -178,6 +330,14 @@ impl<'a> TransformCss<TreeRuleset<'a>> for Tree<'a> {
     }
 }
 
+impl<'a> TransformCss<Span> for Tree<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        for rule in self.0.iter_mut() {
+            rule.transform_each(f);
+        }
+    }
+}
+
 impl<'a> TransformCss<Vec<TreeRuleset<'a>>> for Tree<'a> {
     fn transform_each<F: FnMut(&mut Vec<TreeRuleset<'a>>)>(&mut self, f: &mut F) {
         f(&mut self.0);
@@ -191,19 +351,27 @@ impl<'a> TransformCss<Vec<TreeRuleset<'a>>> for Tree<'a> {
 impl<'a> ParseCss<'a> for Tree<'a> {
     fn parse<E>(input: &'a str) -> IResult<&'a str, Self, E>
     where
-        E: ParserError<&'a str>,
+        E: ParseCssError<'a>,
     {
-        let (input, _) = token::sep0.parse_peek(input)?;
-        let (input, x) = alt((
-            eof.map(|_| vec![]),
-            terminated(
-                repeat(1.., terminated(unpeek(TreeRuleset::parse), token::sep0)),
-                eof,
-            ),
-        ))
-        .parse_peek(input)?;
-
-        Ok((input, Tree(x)))
+        let (rest, _) = (opt('\u{feff}'), token::sep0).parse_peek(input)?;
+        let (rest, x) = terminated(
+            repeat(0.., terminated(unpeek(TreeRuleset::parse), token::sep0)),
+            cut_err(eof).context("selector or at-rule"),
+        )
+        .parse_peek(rest)?;
+
+        let mut tree = Tree(x);
+        let index = LineIndex::new(input);
+        tree.transform(|span: &mut Span| span.resolve(&index));
+        Ok((rest, tree))
+    }
+}
+
+impl<'a> IntoOwned for Tree<'a> {
+    type Owned = Tree<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        Tree(self.0.into_owned())
     }
 }
 
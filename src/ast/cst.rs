@@ -0,0 +1,371 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use std::{borrow::Cow, fmt};
+
+use winnow::Parser;
+
+use super::{
+    flat_ruleset::FlatRule,
+    ruleset::{
+        AtRule, Comment, Keyframe, KeyframeSelector, KeyframesRuleset, QualRule, QualRuleset, Rule,
+        Ruleset, SelectorRuleset,
+    },
+    selector::Selector,
+    span::Span,
+    token::comment0,
+    tree_ruleset::{TreeRule, TreeRuleset},
+    Tree,
+};
+use crate::{
+    owned::IntoOwned,
+    parser::{unwrap_parse_error, ParseCss},
+};
+
+/// A lossless concrete syntax tree, which pairs each node of a parsed [`Tree`]
+/// with the source text it was parsed from, and the _trivia_ (whitespace,
+/// comments and `;`) around it.  Formatting a [`Cst`] with
+/// [`std::fmt::Display`] reproduces the source it was parsed from exactly,
+/// which makes it suitable for codemods and formatters that must preserve
+/// comments.  The text and trivia fields can be replaced to edit the output,
+/// while the AST nodes reflect the original parse.
+///
+/// # Example
+///
+/// ```
+/// use procss::ast::Cst;
+/// let css = "div {\n    /* brand */\n    color: red;\n}\n";
+/// let cst = Cst::parse(css).unwrap();
+/// assert_eq!(cst.to_string(), css);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cst<'a> {
+    pub children: Vec<CstNode<'a>>,
+
+    /// Trivia after the last node.
+    pub trailing: Cow<'a, str>,
+}
+
+/// A node of a [`Cst`], with the trivia which precedes it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CstNode<'a> {
+    pub leading: Cow<'a, str>,
+    pub kind: CstKind<'a>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CstKind<'a> {
+    /// A declaration and its source text, e.g. `color: red`.  The
+    /// terminating `;` is trivia of the following node.
+    Rule(Rule<'a>, Cow<'a, str>),
+
+    /// An at-rule statement and its source text, e.g. `@import "x.css"`.
+    QualRule(QualRule<'a>, Cow<'a, str>),
+
+    /// A ruleset or at-rule with a block.
+    Block(Box<CstBlock<'a>>),
+
+    /// A `/* */` comment and its source text.
+    Comment(Comment<'a>, Cow<'a, str>),
+}
+
+/// A ruleset or at-rule with a `{}` block of [`CstNode`] children.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CstBlock<'a> {
+    pub prelude: CstPrelude<'a>,
+
+    /// The source text of the prelude through the opening `{`.
+    pub text: Cow<'a, str>,
+    pub children: Vec<CstNode<'a>>,
+
+    /// Trivia after the last child, before the closing `}`.
+    pub trailing: Cow<'a, str>,
+    pub span: Span,
+}
+
+/// The part of a [`CstBlock`] before its `{`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CstPrelude<'a> {
+    Selector(Selector<'a>),
+    QualRule(QualRule<'a>),
+
+    /// The selectors of a frame of an `@keyframes` block.
+    Keyframe(Vec<KeyframeSelector<'a>>),
+}
+
+impl<'a> Cst<'a> {
+    /// Parse CSS text to a [`Cst`].
+    pub fn parse(input: &'a str) -> anyhow::Result<Self> {
+        let (_, tree) = Tree::parse(input).map_err(|err| unwrap_parse_error(input, err, None))?;
+        let nodes = tree.iter().map(|x| ruleset(input, x));
+        let (children, trailing) = children(input, 0, input.len(), nodes);
+        Ok(Cst { children, trailing })
+    }
+
+    /// The [`Tree`] this [`Cst`] was parsed from.
+    pub fn to_tree(&self) -> Tree<'a> {
+        Tree(
+            self.children
+                .iter()
+                .filter_map(|x| match x.to_tree_rule() {
+                    TreeRule::Ruleset(ruleset) => Some(*ruleset),
+                    TreeRule::Comment(comment) => Some(Ruleset::Comment(comment)),
+                    TreeRule::Rule(_) => None,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl<'a> CstNode<'a> {
+    fn to_tree_rule(&self) -> TreeRule<'a> {
+        match &self.kind {
+            CstKind::Rule(rule, _) => TreeRule::Rule(rule.clone()),
+            CstKind::QualRule(rule, _) => Ruleset::QualRule(rule.clone()).into(),
+            CstKind::Block(block) => block.to_tree_ruleset().into(),
+            CstKind::Comment(comment, _) => TreeRule::Comment(comment.clone()),
+        }
+    }
+}
+
+impl<'a> CstBlock<'a> {
+    fn to_tree_ruleset(&self) -> TreeRuleset<'a> {
+        let rules = || self.children.iter().map(|x| x.to_tree_rule()).collect();
+        match &self.prelude {
+            CstPrelude::QualRule(rule) if matches!(rule.at_rule(), AtRule::Keyframes(_)) => {
+                let keyframes = self
+                    .children
+                    .iter()
+                    .filter_map(|x| match &x.kind {
+                        CstKind::Block(block) => block.to_keyframe(),
+                        _ => None,
+                    })
+                    .collect();
+
+                Ruleset::Keyframes(KeyframesRuleset(rule.clone(), keyframes, self.span))
+            }
+            CstPrelude::QualRule(rule) => {
+                Ruleset::QualRuleset(QualRuleset(rule.clone(), rules(), self.span))
+            }
+            CstPrelude::Selector(selector) => {
+                Ruleset::SelectorRuleset(SelectorRuleset(selector.clone(), rules(), self.span))
+            }
+
+            // A frame outside of an `@keyframes` block can only be built by
+            // hand, and has no selector to apply to.
+            CstPrelude::Keyframe(_) => {
+                Ruleset::SelectorRuleset(SelectorRuleset(Selector::default(), rules(), self.span))
+            }
+        }
+    }
+
+    fn to_keyframe(&self) -> Option<Keyframe<'a>> {
+        let CstPrelude::Keyframe(selectors) = &self.prelude else {
+            return None;
+        };
+
+        let rules = self
+            .children
+            .iter()
+            .filter_map(|x| match &x.kind {
+                CstKind::Rule(rule, _) => Some(FlatRule::Rule(rule.clone())),
+                CstKind::Comment(comment, _) => Some(FlatRule::Comment(comment.clone())),
+                _ => None,
+            })
+            .collect();
+
+        Some(Keyframe(selectors.clone(), rules, self.span))
+    }
+}
+
+impl<'a> IntoOwned for Cst<'a> {
+    type Owned = Cst<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        Cst {
+            children: self.children.into_owned(),
+            trailing: self.trailing.into_owned().into(),
+        }
+    }
+}
+
+impl<'a> IntoOwned for CstNode<'a> {
+    type Owned = CstNode<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        CstNode {
+            leading: self.leading.into_owned().into(),
+            kind: match self.kind {
+                CstKind::Rule(x, text) => CstKind::Rule(x.into_owned(), text.into_owned().into()),
+                CstKind::QualRule(x, text) => {
+                    CstKind::QualRule(x.into_owned(), text.into_owned().into())
+                }
+                CstKind::Block(x) => CstKind::Block(Box::new((*x).into_owned())),
+                CstKind::Comment(x, text) => {
+                    CstKind::Comment(x.into_owned(), text.into_owned().into())
+                }
+            },
+        }
+    }
+}
+
+impl<'a> IntoOwned for CstBlock<'a> {
+    type Owned = CstBlock<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        CstBlock {
+            prelude: match self.prelude {
+                CstPrelude::Selector(x) => CstPrelude::Selector(x.into_owned()),
+                CstPrelude::QualRule(x) => CstPrelude::QualRule(x.into_owned()),
+                CstPrelude::Keyframe(x) => CstPrelude::Keyframe(x.into_owned()),
+            },
+            text: self.text.into_owned().into(),
+            children: self.children.into_owned(),
+            trailing: self.trailing.into_owned().into(),
+            span: self.span,
+        }
+    }
+}
+
+impl<'a> fmt::Display for Cst<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in self.children.iter() {
+            child.fmt(f)?;
+        }
+
+        write!(f, "{}", self.trailing)
+    }
+}
+
+impl<'a> fmt::Display for CstNode<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.leading)?;
+        match &self.kind {
+            CstKind::Rule(_, text) | CstKind::QualRule(_, text) | CstKind::Comment(_, text) => {
+                write!(f, "{}", text)
+            }
+            CstKind::Block(block) => block.fmt(f),
+        }
+    }
+}
+
+impl<'a> fmt::Display for CstBlock<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)?;
+        for child in self.children.iter() {
+            child.fmt(f)?;
+        }
+
+        write!(f, "{}}}", self.trailing)
+    }
+}
+
+/// Collect `nodes` into [`CstNode`]s, assigning the source between them from
+/// `start` to `end` as trivia.
+fn children<'a>(
+    src: &'a str,
+    start: usize,
+    end: usize,
+    nodes: impl Iterator<Item = (Span, CstKind<'a>)>,
+) -> (Vec<CstNode<'a>>, Cow<'a, str>) {
+    let mut offset = start;
+    let mut children = vec![];
+    for (span, kind) in nodes {
+        let leading = src[offset..span.start.offset].into();
+        children.push(CstNode { leading, kind });
+        offset = span.end.offset;
+    }
+
+    (children, src[offset..end].into())
+}
+
+fn text(src: &str, span: Span) -> Cow<'_, str> {
+    src[span.start.offset..span.end.offset].into()
+}
+
+fn rule<'a>(src: &'a str, rule: &TreeRule<'a>) -> (Span, CstKind<'a>) {
+    match rule {
+        TreeRule::Rule(rule) => (rule.span, CstKind::Rule(rule.clone(), text(src, rule.span))),
+        TreeRule::Ruleset(ruleset) => self::ruleset(src, ruleset),
+        TreeRule::Comment(x) => (x.span, CstKind::Comment(x.clone(), text(src, x.span))),
+    }
+}
+
+fn ruleset<'a>(src: &'a str, ruleset: &TreeRuleset<'a>) -> (Span, CstKind<'a>) {
+    match ruleset {
+        Ruleset::QualRule(rule) => (rule.2, CstKind::QualRule(rule.clone(), text(src, rule.2))),
+        Ruleset::Comment(x) => (x.span, CstKind::Comment(x.clone(), text(src, x.span))),
+        Ruleset::SelectorRuleset(SelectorRuleset(selector, rules, span)) => {
+            let rest = &src[selector.span().end.offset..];
+            let rest = comment0::<()>.parse_peek(rest).map_or(rest, |(x, _)| x);
+            let open = src.len() - rest.len() + 1;
+            let nodes = rules.iter().map(|x| rule(src, x));
+            let prelude = CstPrelude::Selector(selector.clone());
+            block(src, *span, open, prelude, nodes)
+        }
+        Ruleset::QualRuleset(QualRuleset(qual_rule, rules, span)) => {
+            let open = qual_rule.2.end.offset + 1;
+            let nodes = rules.iter().map(|x| rule(src, x));
+            let prelude = CstPrelude::QualRule(qual_rule.clone());
+            block(src, *span, open, prelude, nodes)
+        }
+        Ruleset::Keyframes(KeyframesRuleset(qual_rule, keyframes, span)) => {
+            let open = qual_rule.2.end.offset + 1;
+            let nodes = keyframes.iter().map(|x| keyframe(src, x));
+            let prelude = CstPrelude::QualRule(qual_rule.clone());
+            block(src, *span, open, prelude, nodes)
+        }
+        Ruleset::QualNestedRuleset(ruleset) => {
+            let open = ruleset.0 .2.end.offset + 1;
+            let nodes = ruleset.1.iter().map(|x| self::ruleset(src, x));
+            let prelude = CstPrelude::QualRule(ruleset.0.clone());
+            block(src, ruleset.2, open, prelude, nodes)
+        }
+    }
+}
+
+fn keyframe<'a>(src: &'a str, keyframe: &Keyframe<'a>) -> (Span, CstKind<'a>) {
+    let Keyframe(selectors, rules, span) = keyframe;
+    let open = src[span.start.offset..].find('{').unwrap_or_default() + span.start.offset + 1;
+    let nodes = rules.iter().map(|x| match x {
+        FlatRule::Rule(rule) => (rule.span, CstKind::Rule(rule.clone(), text(src, rule.span))),
+        FlatRule::Comment(x) => (x.span, CstKind::Comment(x.clone(), text(src, x.span))),
+    });
+
+    let prelude = CstPrelude::Keyframe(selectors.clone());
+    block(src, *span, open, prelude, nodes)
+}
+
+fn block<'a>(
+    src: &'a str,
+    span: Span,
+    open: usize,
+    prelude: CstPrelude<'a>,
+    nodes: impl Iterator<Item = (Span, CstKind<'a>)>,
+) -> (Span, CstKind<'a>) {
+    let (children, trailing) = children(src, open, span.end.offset - 1, nodes);
+    let text = src[span.start.offset..open].into();
+    (
+        span,
+        CstKind::Block(Box::new(CstBlock {
+            prelude,
+            text,
+            children,
+            trailing,
+            span,
+        })),
+    )
+}
@@ -9,11 +9,82 @@
 // │                                                                           │
 // └───────────────────────────────────────────────────────────────────────────┘
 
-use super::ruleset::{Rule, Ruleset};
+use super::ruleset::{Comment, Rule, Ruleset};
 use super::selector::SelectorPath;
+use super::span::Span;
 use super::SelectorRuleset;
+use crate::owned::IntoOwned;
+use crate::render::RenderCss;
 use crate::transform::TransformCss;
 
+/// The contents of a [`FlatRuleset`], which unlike a
+/// [`super::TreeRule`] may not contain nested rulesets.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlatRule<'a> {
+    Rule(Rule<'a>),
+    Comment(Comment<'a>),
+}
+
+impl<'a> From<Rule<'a>> for FlatRule<'a> {
+    fn from(rule: Rule<'a>) -> Self {
+        FlatRule::Rule(rule)
+    }
+}
+
+impl<'a> From<Comment<'a>> for FlatRule<'a> {
+    fn from(comment: Comment<'a>) -> Self {
+        FlatRule::Comment(comment)
+    }
+}
+
+impl<'a> RenderCss for FlatRule<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlatRule::Rule(rule) => rule.render(f),
+            FlatRule::Comment(comment) => comment.render(f),
+        }
+    }
+}
+
+impl<'a> IntoOwned for FlatRule<'a> {
+    type Owned = FlatRule<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            FlatRule::Rule(rule) => FlatRule::Rule(rule.into_owned()),
+            FlatRule::Comment(comment) => FlatRule::Comment(comment.into_owned()),
+        }
+    }
+}
+
+impl<'a> TransformCss<Rule<'a>> for FlatRule<'a> {
+    fn transform_each<F: FnMut(&mut Rule<'a>)>(&mut self, f: &mut F) {
+        match self {
+            FlatRule::Rule(rule) => f(rule),
+            FlatRule::Comment(_) => (),
+        }
+    }
+}
+
+impl<'a> TransformCss<Comment<'a>> for FlatRule<'a> {
+    fn transform_each<F: FnMut(&mut Comment<'a>)>(&mut self, f: &mut F) {
+        match self {
+            FlatRule::Rule(_) => (),
+            FlatRule::Comment(comment) => f(comment),
+        }
+    }
+}
+
+impl<'a> TransformCss<Span> for FlatRule<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        match self {
+            FlatRule::Rule(rule) => rule.transform_each(f),
+            FlatRule::Comment(comment) => comment.transform_each(f),
+        }
+    }
+}
+
 /// A flat (non-recursive) block, suitable for compatibility with modern
 /// browsers.
 ///
@@ -28,7 +99,7 @@ use crate::transform::TransformCss;
 ///     color: purple;
 /// }
 /// ```
-pub type FlatRuleset<'a> = Ruleset<'a, Rule<'a>>;
+pub type FlatRuleset<'a> = Ruleset<'a, FlatRule<'a>>;
 
 impl<'a> TransformCss<SelectorPath<'a>> for FlatRuleset<'a> {
     fn transform_each<F: FnMut(&mut SelectorPath<'a>)>(&mut self, f: &mut F) {
@@ -37,12 +108,14 @@ impl<'a> TransformCss<SelectorPath<'a>> for FlatRuleset<'a> {
             Ruleset::QualRule(_) => (),
             Ruleset::QualRuleset(_) => (),
             Ruleset::QualNestedRuleset(ruleset) => ruleset.transform_each(f),
+            Ruleset::Keyframes(_) => (),
+            Ruleset::Comment(_) => (),
         }
     }
 }
 
-impl<'a> TransformCss<SelectorRuleset<'a, Rule<'a>>> for FlatRuleset<'a> {
-    fn transform_each<F: FnMut(&mut SelectorRuleset<'a, Rule<'a>>)>(&mut self, f: &mut F) {
+impl<'a> TransformCss<SelectorRuleset<'a, FlatRule<'a>>> for FlatRuleset<'a> {
+    fn transform_each<F: FnMut(&mut SelectorRuleset<'a, FlatRule<'a>>)>(&mut self, f: &mut F) {
         match self {
             Ruleset::SelectorRuleset(ruleset) => f(ruleset),
             Ruleset::QualNestedRuleset(ruleset) => ruleset.transform_each(f),
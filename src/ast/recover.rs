@@ -0,0 +1,337 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+//! An error-recovering parser for [`super::Tree`], which skips invalid
+//! declarations and rulesets the way browsers do, rather than failing on the
+//! first error.  Each ruleset and declaration is parsed with the strict
+//! [`ParseCss`] parsers first, and only the block structure of those which
+//! fail is walked by hand, re-using the strict parsers for their selectors,
+//! at-rule preludes and children.
+
+use winnow::{
+    combinator::{cut_err, opt},
+    error::{AddContext, ErrMode},
+    token::tag,
+    IResult, Parser,
+};
+
+use super::{
+    ruleset::{
+        AtRule, Comment, Keyframe, KeyframesRuleset, QualRule, QualRuleset, Ruleset,
+        SelectorRuleset,
+    },
+    selector::Selector,
+    span::Span,
+    token::{comment0, sep0},
+    tree_ruleset::{invalid_suffix, nest_prefix, TreeRule, TreeRuleset, SUFFIX_CONTEXT},
+};
+use crate::parser::{into_parse_error, ParseCss, ParseCssError, ParseError};
+
+/// Collects the [`ParseError`]s encountered while parsing `src`.
+pub(crate) struct Recover<'a> {
+    src: &'a str,
+    errors: Vec<ParseError>,
+}
+
+impl<'a> Recover<'a> {
+    pub(crate) fn new(src: &'a str) -> Self {
+        Recover {
+            src,
+            errors: vec![],
+        }
+    }
+
+    pub(crate) fn into_errors(self) -> Vec<ParseError> {
+        self.errors
+    }
+
+    /// Parse a stylesheet, skipping any rulesets which fail to parse, as well
+    /// as unmatched `}`.
+    pub(crate) fn tree(&mut self) -> Vec<TreeRuleset<'a>> {
+        let mut rulesets = vec![];
+        let mut rest = self.src;
+        loop {
+            rest = skip_sep(rest);
+            if rest.is_empty() {
+                return rulesets;
+            } else if rest.starts_with('}') {
+                self.error_at(rest, "expected selector or at-rule");
+                rest = &rest[1..];
+            } else if rest.starts_with("/*") {
+                let (next, comment) = self.comment(rest);
+                rulesets.extend(comment.map(Ruleset::Comment));
+                rest = next;
+            } else if let Ok((next, ruleset)) = TreeRuleset::parse::<()>(rest) {
+                rulesets.push(ruleset);
+                rest = next;
+            } else {
+                let (next, ruleset) = self.ruleset(rest);
+                rulesets.extend(ruleset);
+                rest = next;
+            }
+        }
+    }
+
+    /// Parse a ruleset or at-rule which failed to parse strictly, recovering
+    /// from errors in its block, or skip to the end of the statement (past the
+    /// next `;` or block) if it is invalid.
+    fn ruleset(&mut self, input: &'a str) -> (&'a str, Option<TreeRuleset<'a>>) {
+        match scan(input) {
+            Some((idx, '{')) => match prelude(input) {
+                Ok((rest, Prelude::Selector(selector))) => {
+                    let (rest, rules) = self.block(rest);
+                    if let Some(ruleset) = invalid_suffix(&selector, &rules) {
+                        let at = &input[input.len() - ruleset.2.start.offset..];
+                        self.error_at(at, &format!("expected {}", SUFFIX_CONTEXT));
+                    }
+
+                    let span = Span::parsed(input, rest);
+                    let ruleset = SelectorRuleset(selector, rules, span);
+                    (rest, Some(Ruleset::SelectorRuleset(ruleset)))
+                }
+                Ok((rest, Prelude::QualRule(qual_rule)))
+                    if matches!(qual_rule.at_rule(), AtRule::Keyframes(_)) =>
+                {
+                    let (rest, keyframes) = self.keyframes(rest);
+                    let span = Span::parsed(input, rest);
+                    let ruleset = KeyframesRuleset(qual_rule, keyframes, span);
+                    (rest, Some(Ruleset::Keyframes(ruleset)))
+                }
+                Ok((rest, Prelude::QualRule(qual_rule))) => {
+                    let (rest, rules) = self.block(rest);
+                    let span = Span::parsed(input, rest);
+                    let ruleset = QualRuleset(qual_rule, rules, span);
+                    (rest, Some(Ruleset::QualRuleset(ruleset)))
+                }
+                Err(err) => {
+                    self.errors.push(into_parse_error(self.src, err, None));
+                    (skip_block(&input[idx..]), None)
+                }
+            },
+            _ => match TreeRuleset::parse(input) {
+                Ok((rest, ruleset)) => (rest, Some(ruleset)),
+                Err(err) => {
+                    self.errors.push(into_parse_error(self.src, err, None));
+                    (skip_statement(input), None)
+                }
+            },
+        }
+    }
+
+    /// Parse the contents of a block after its opening `{`, through its
+    /// closing `}`, skipping any declarations and nested rulesets which fail
+    /// to parse.  A block left open at the end of the input is closed.
+    fn block(&mut self, input: &'a str) -> (&'a str, Vec<TreeRule<'a>>) {
+        let mut rules = vec![];
+        let mut rest = input;
+        loop {
+            rest = skip_sep(rest);
+            if rest.is_empty() {
+                self.error_at(rest, "expected declaration, ruleset or `}`");
+                return (rest, rules);
+            } else if let Some(rest) = rest.strip_prefix('}') {
+                return (rest, rules);
+            } else if rest.starts_with("/*") {
+                let (next, comment) = self.comment(rest);
+                rules.extend(comment.map(TreeRule::Comment));
+                rest = next;
+            } else {
+                match TreeRule::parse(rest) {
+                    Ok((next, rule)) => {
+                        rules.push(rule);
+                        rest = next;
+                    }
+                    Err(_) if !rest.starts_with("--") && matches!(scan(rest), Some((_, '{'))) => {
+                        let (next, ruleset) = self.ruleset(rest);
+                        rules.extend(ruleset.map(TreeRule::from));
+                        rest = next;
+                    }
+                    Err(err) => {
+                        self.errors.push(into_parse_error(self.src, err, None));
+                        rest = skip_statement(rest);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parse the frames of an `@keyframes` block after its opening `{`,
+    /// through its closing `}`, skipping any frames which fail to parse.
+    fn keyframes(&mut self, input: &'a str) -> (&'a str, Vec<Keyframe<'a>>) {
+        let mut keyframes = vec![];
+        let mut rest = input;
+        loop {
+            rest = skip_sep(rest);
+            if rest.is_empty() {
+                self.error_at(rest, "expected keyframe selector or `}`");
+                return (rest, keyframes);
+            } else if let Some(rest) = rest.strip_prefix('}') {
+                return (rest, keyframes);
+            } else if rest.starts_with("/*") {
+                rest = self.comment(rest).0;
+            } else {
+                match Keyframe::parse(rest) {
+                    Ok((next, keyframe)) => {
+                        keyframes.push(keyframe);
+                        rest = next;
+                    }
+                    Err(err) => {
+                        self.errors.push(into_parse_error(self.src, err, None));
+                        rest = skip_statement(rest);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Parse a comment, or skip the rest of the input if it is unterminated.
+    fn comment(&mut self, input: &'a str) -> (&'a str, Option<Comment<'a>>) {
+        match Comment::parse::<()>(input) {
+            Ok((rest, comment)) => (rest, Some(comment)),
+            Err(_) => {
+                self.error_at("", "expected `*/`");
+                ("", None)
+            }
+        }
+    }
+
+    fn error_at(&mut self, rest: &'a str, message: &str) {
+        let offset = self.src.len() - rest.len();
+        let error = ParseError::new(self.src, offset, message.to_owned(), None);
+        self.errors.push(error);
+    }
+}
+
+/// The part of a ruleset before its block.
+enum Prelude<'a> {
+    Selector(Selector<'a>),
+    QualRule(QualRule<'a>),
+}
+
+/// Parse a ruleset's prelude through its opening `{`.
+fn prelude<'a, E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Prelude<'a>, E> {
+    if input.starts_with('@') && nest_prefix::<()>.parse_peek(input).is_err() {
+        let (rest, qual_rule) = QualRule::parse(input)?;
+        let (rest, _) = cut_err(tag("{"))
+            .parse_peek(rest)
+            .map_err(|err| err.add_context(&input, "`{` after at-rule"))?;
+
+        Ok((rest, Prelude::QualRule(qual_rule)))
+    } else {
+        let (rest, _) = opt(nest_prefix).parse_peek(input)?;
+        let (rest, selector) =
+            Selector::parse(rest).map_err(|err: ErrMode<E>| err.add_context(&input, "selector"))?;
+
+        let (rest, _) = (comment0, cut_err(tag("{")))
+            .parse_peek(rest)
+            .map_err(|err| err.add_context(&input, "`{` after selector"))?;
+
+        Ok((rest, Prelude::Selector(selector)))
+    }
+}
+
+fn skip_sep(input: &str) -> &str {
+    sep0::<()>.parse_peek(input).map_or(input, |(rest, _)| rest)
+}
+
+/// Find the first `;`, `{` or `}` in `input` which is not inside a string,
+/// comment or parentheses.
+fn scan(input: &str) -> Option<(usize, char)> {
+    let mut depth = 0_usize;
+    let mut chars = input.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' | '\'' => skip_string(&mut chars, c),
+            '/' if matches!(chars.peek(), Some((_, '*'))) => {
+                chars.next();
+                let mut prev = ' ';
+                for (_, c) in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+
+                    prev = c;
+                }
+            }
+            '/' if depth == 0 && matches!(chars.peek(), Some((_, '/'))) => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            ';' | '{' | '}' if depth == 0 => return Some((idx, c)),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+fn skip_string(chars: &mut impl Iterator<Item = (usize, char)>, quote: char) {
+    let mut escaped = false;
+    for (_, c) in chars {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '\n' => return,
+            _ if c == quote => return,
+            _ => (),
+        }
+    }
+}
+
+/// Skip past the end of a statement, which ends with a `;` or a block, or
+/// before the `}` which ends the enclosing block.
+fn skip_statement(input: &str) -> &str {
+    match scan(input) {
+        Some((idx, ';')) => &input[idx + 1..],
+        Some((idx, '{')) => skip_block(&input[idx..]),
+        Some((idx, _)) => &input[idx..],
+        None => "",
+    }
+}
+
+/// Skip past the block which starts at the `{` at the head of `input`,
+/// including nested blocks.
+fn skip_block(input: &str) -> &str {
+    let mut rest = &input[1..];
+    loop {
+        match scan(rest) {
+            Some((idx, '{')) => rest = skip_block(&rest[idx..]),
+            Some((idx, '}')) => return &rest[idx + 1..],
+            Some((idx, _)) => rest = &rest[idx + 1..],
+            None => return "",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        assert_eq!(scan("color: red; }"), Some((10, ';')));
+        assert_eq!(scan("a[title=\"{\"] {"), Some((13, '{')));
+        assert_eq!(scan("content: \";\" /* ; */}"), Some((20, '}')));
+        assert_eq!(scan("background: url(data:;base64) }"), Some((30, '}')));
+        assert_eq!(scan("color: red"), None);
+    }
+
+    #[test]
+    fn test_skip_block() {
+        assert_eq!(skip_block("{ a { b: c; } d: \"}\" } rest"), " rest");
+        assert_eq!(skip_block("{ a { b: c; }"), "");
+    }
+}
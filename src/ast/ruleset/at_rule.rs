@@ -0,0 +1,615 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use winnow::{
+    ascii::{multispace0, multispace1},
+    combinator::{alt, delimited, opt, preceded, repeat, separated, terminated},
+    error::ParserError,
+    token::tag_no_case,
+    unpeek, PResult, Parser,
+};
+
+use super::{media::media_query_list, MediaQuery, QualRule};
+use crate::{
+    ast::{selector::Selector, token::parse_ident, ComponentValue},
+    parser::ParseCss,
+};
+
+/// A typed at-rule, parsed from the name and prelude of a [`QualRule`] by
+/// [`QualRule::at_rule`].  Names are matched case-insensitively, and at-rules
+/// which are unknown or whose prelude is invalid are [`AtRule::Unknown`].
+///
+/// ```
+/// use procss::ast::{AtRule, QualRule, Ruleset};
+/// let tree = procss::parse("@layer base, components;").unwrap();
+/// let Ruleset::QualRule(qual_rule) = &tree.0[0] else {
+///     panic!()
+/// };
+///
+/// assert_eq!(
+///     qual_rule.at_rule(),
+///     AtRule::Layer(vec!["base", "components"])
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum AtRule<'a> {
+    /// `@charset "utf-8";`, by the contents of its string.
+    Charset(&'a str),
+
+    /// `@import url("theme.css") layer(base) screen;`
+    Import(Import<'a>),
+
+    /// `@namespace svg url(http://www.w3.org/2000/svg);`, by its prefix (if
+    /// any) and URL.
+    Namespace(Option<&'a str>, &'a str),
+
+    /// `@media screen, print`, by its comma-separated media queries.
+    Media(Vec<MediaQuery<'a>>),
+
+    /// `@supports (display: grid) and (not (display: inline-grid))`
+    Supports(SupportsCondition<'a>),
+
+    /// `@font-face`
+    FontFace,
+
+    /// `@keyframes spin`, by name.  Vendor-prefixed `@-webkit-keyframes` etc.
+    /// are also [`AtRule::Keyframes`].
+    Keyframes(&'a str),
+
+    /// `@page :first`, by its page selectors, which may be empty.
+    Page(Vec<PageSelector<'a>>),
+
+    /// A margin box of a `@page`, e.g. `@top-left`.
+    PageMargin(PageMargin),
+
+    /// `@layer base, components;`, by its layer names, or an anonymous
+    /// `@layer { .. }` if empty.
+    Layer(Vec<&'a str>),
+
+    /// `@container sidebar (min-width: 400px)`, by its name (if any) and its
+    /// container condition as written.
+    Container(Option<&'a str>, &'a str),
+
+    /// `@property --angle`, by its custom property name.
+    Property(&'a str),
+
+    /// A procss `@mixin name { .. }` definition, see
+    /// [`crate::transformers::apply_mixin`].
+    Mixin(&'a str),
+
+    /// A procss `@include name;` of a mixin, see
+    /// [`crate::transformers::apply_mixin`].
+    Include(&'a str),
+
+    /// A procss `@name: value;` variable, by its name and trimmed value, see
+    /// [`crate::transformers::apply_var`].  Names of CSS at-rules are never
+    /// variables, e.g. `@page :first`.
+    Variable(&'a str, &'a str),
+
+    /// Any other at-rule, by its name and raw prelude.
+    Unknown(&'a str, Option<&'a str>),
+}
+
+/// The prelude of an `@import`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Import<'a> {
+    /// The imported stylesheet, either a [`ComponentValue::String`] or a
+    /// `url()`.
+    pub url: ComponentValue<'a>,
+
+    /// The cascade layer of `layer(name)`, or `Some("")` for an anonymous
+    /// `layer`.
+    pub layer: Option<&'a str>,
+
+    /// The condition of `supports(..)`.
+    pub supports: Option<SupportsCondition<'a>>,
+
+    /// The comma-separated media queries this import applies to.
+    pub media: Vec<MediaQuery<'a>>,
+}
+
+/// The condition of an `@supports` or `@import .. supports(..)`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SupportsCondition<'a> {
+    Not(Box<SupportsCondition<'a>>),
+    And(Vec<SupportsCondition<'a>>),
+    Or(Vec<SupportsCondition<'a>>),
+
+    /// A `(property: value)` declaration test.
+    Declaration(&'a str, &'a str),
+
+    /// A `selector(..)` test.
+    Selector(Box<Selector<'a>>),
+
+    /// Any other function or parenthesized test, as written, which is
+    /// reserved by the spec for future use.
+    Raw(&'a str),
+}
+
+/// A page selector of a `@page`, e.g. `cover:first`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PageSelector<'a> {
+    pub name: Option<&'a str>,
+
+    /// The page pseudo-classes (without the `:`), e.g. `first`.
+    pub pseudo: Vec<&'a str>,
+}
+
+/// The margin boxes which may be nested in a `@page`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum PageMargin {
+    TopLeftCorner,
+    TopLeft,
+    TopCenter,
+    TopRight,
+    TopRightCorner,
+    BottomLeftCorner,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+    BottomRightCorner,
+    LeftTop,
+    LeftMiddle,
+    LeftBottom,
+    RightTop,
+    RightMiddle,
+    RightBottom,
+}
+
+const PAGE_MARGINS: [(&str, PageMargin); 16] = [
+    ("top-left-corner", PageMargin::TopLeftCorner),
+    ("top-left", PageMargin::TopLeft),
+    ("top-center", PageMargin::TopCenter),
+    ("top-right", PageMargin::TopRight),
+    ("top-right-corner", PageMargin::TopRightCorner),
+    ("bottom-left-corner", PageMargin::BottomLeftCorner),
+    ("bottom-left", PageMargin::BottomLeft),
+    ("bottom-center", PageMargin::BottomCenter),
+    ("bottom-right", PageMargin::BottomRight),
+    ("bottom-right-corner", PageMargin::BottomRightCorner),
+    ("left-top", PageMargin::LeftTop),
+    ("left-middle", PageMargin::LeftMiddle),
+    ("left-bottom", PageMargin::LeftBottom),
+    ("right-top", PageMargin::RightTop),
+    ("right-middle", PageMargin::RightMiddle),
+    ("right-bottom", PageMargin::RightBottom),
+];
+
+impl PageMargin {
+    /// The at-rule name of this margin box, e.g. `top-left`.
+    pub fn as_str(&self) -> &'static str {
+        PAGE_MARGINS.iter().find(|x| x.1 == *self).unwrap().0
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        PAGE_MARGINS
+            .iter()
+            .find(|x| x.0.eq_ignore_ascii_case(name))
+            .map(|x| x.1)
+    }
+}
+
+impl<'a> QualRule<'a> {
+    /// Parse this at-rule's name and prelude into an [`AtRule`].
+    pub fn at_rule(&self) -> AtRule<'_> {
+        let QualRule(name, prelude, _) = self;
+        let prelude = prelude.as_deref();
+        let unknown = AtRule::Unknown(name, prelude);
+        if let Some(value) = prelude.and_then(|x| x.strip_prefix(':')) {
+            if !is_at_rule_name(name) {
+                return AtRule::Variable(name, value.trim());
+            }
+        }
+
+        let text = prelude.unwrap_or_default().trim();
+        let at_rule = match name.to_ascii_lowercase().as_str() {
+            "charset" => single_value(text)
+                .and_then(|x| x.string())
+                .map(AtRule::Charset),
+            "import" => parse_all(text, import).map(AtRule::Import),
+            "namespace" => namespace(text).map(|(prefix, url)| AtRule::Namespace(prefix, url)),
+            "media" => parse_all(text, media_query_list).map(AtRule::Media),
+            "supports" => parse_all(text, supports_condition).map(AtRule::Supports),
+            "font-face" if text.is_empty() => Some(AtRule::FontFace),
+            "keyframes" | "-webkit-keyframes" | "-moz-keyframes" | "-o-keyframes" => {
+                single_value(text)
+                    .and_then(|x| match x {
+                        ComponentValue::Ident(x) => Some(x),
+                        x => x.string(),
+                    })
+                    .map(AtRule::Keyframes)
+            }
+            "page" => parse_all(
+                text,
+                separated(0.., page_selector, (multispace0, ',', multispace0)),
+            )
+            .map(AtRule::Page),
+            "layer" => parse_all(
+                text,
+                separated(0.., layer_name, (multispace0, ',', multispace0)),
+            )
+            .map(AtRule::Layer),
+            "container" => parse_all(text, container).map(|(x, y)| AtRule::Container(x, y)),
+            "property" if text.starts_with("--") => {
+                parse_all(text, parse_ident).map(AtRule::Property)
+            }
+            "mixin" if !text.is_empty() => Some(AtRule::Mixin(text)),
+            "include" if !text.is_empty() => Some(AtRule::Include(text)),
+            _ if text.is_empty() => PageMargin::from_name(name).map(AtRule::PageMargin),
+            _ => None,
+        };
+
+        at_rule.unwrap_or(unknown)
+    }
+}
+
+/// The CSS (and procss) at-rules, which are not parsed as `@name: value;`
+/// variables even if their prelude starts with `:`.
+const AT_RULE_NAMES: &[&str] = &[
+    "charset",
+    "import",
+    "namespace",
+    "media",
+    "supports",
+    "font-face",
+    "keyframes",
+    "-webkit-keyframes",
+    "-moz-keyframes",
+    "-o-keyframes",
+    "page",
+    "layer",
+    "container",
+    "property",
+    "counter-style",
+    "font-feature-values",
+    "font-palette-values",
+    "document",
+    "-moz-document",
+    "viewport",
+    "scope",
+    "starting-style",
+    "position-try",
+    "view-transition",
+    "nest",
+    "mixin",
+    "include",
+];
+
+fn is_at_rule_name(name: &str) -> bool {
+    AT_RULE_NAMES.iter().any(|x| x.eq_ignore_ascii_case(name))
+        || PageMargin::from_name(name).is_some()
+}
+
+/// Run `parser` on all of `input`.
+fn parse_all<'a, O>(input: &'a str, mut parser: impl Parser<&'a str, O, ()>) -> Option<O> {
+    parser.parse(input).ok()
+}
+
+/// The only (non-whitespace) [`ComponentValue`] of `input`.
+fn single_value(input: &str) -> Option<ComponentValue<'_>> {
+    let values = ComponentValue::parse_list(input);
+    let mut values = values
+        .into_iter()
+        .filter(|x| *x != ComponentValue::Whitespace);
+    match (values.next(), values.next()) {
+        (Some(x), None) => Some(x),
+        _ => None,
+    }
+}
+
+/// The prefix (if any) and URL of a `@namespace` prelude.
+fn namespace(input: &str) -> Option<(Option<&str>, &str)> {
+    let values = ComponentValue::parse_list(input);
+    let mut values = values.iter().filter(|x| **x != ComponentValue::Whitespace);
+    match (values.next(), values.next(), values.next()) {
+        (Some(ComponentValue::Ident(prefix)), Some(url), None) => {
+            Some((Some(*prefix), url.url().or(url.string())?))
+        }
+        (Some(url), None, None) => Some((None, url.url().or(url.string())?)),
+        _ => None,
+    }
+}
+
+fn import<'a>(input: &mut &'a str) -> PResult<Import<'a>, ()> {
+    let url = unpeek(ComponentValue::parse)
+        .verify(|x: &ComponentValue| x.string().is_some() || x.url().is_some())
+        .parse_next(input)?;
+
+    multispace0.parse_next(input)?;
+    let layer = opt(terminated(
+        alt((
+            delimited(
+                tag_no_case("layer("),
+                delimited(multispace0, layer_name, multispace0),
+                ')',
+            ),
+            tag_no_case("layer").value(""),
+        )),
+        multispace0,
+    ))
+    .parse_next(input)?;
+
+    let supports = opt(terminated(
+        delimited(
+            tag_no_case("supports("),
+            delimited(
+                multispace0,
+                alt((supports_condition, declaration)),
+                multispace0,
+            ),
+            ')',
+        ),
+        multispace0,
+    ))
+    .parse_next(input)?;
+
+    let media = media_query_list.parse_next(input)?;
+    Ok(Import {
+        url,
+        layer,
+        supports,
+        media,
+    })
+}
+
+fn supports_condition<'a>(input: &mut &'a str) -> PResult<SupportsCondition<'a>, ()> {
+    let not = preceded((tag_no_case("not"), multispace1), supports_in_parens)
+        .map(|x| SupportsCondition::Not(Box::new(x)));
+
+    alt((not, supports_chain)).parse_next(input)
+}
+
+/// One or more tests joined by either `and` or `or`, which may not be mixed
+/// without parentheses.
+fn supports_chain<'a>(input: &mut &'a str) -> PResult<SupportsCondition<'a>, ()> {
+    let first = supports_in_parens.parse_next(input)?;
+    let op = || {
+        delimited(
+            multispace1,
+            alt((tag_no_case("and"), tag_no_case("or"))),
+            multispace1,
+        )
+    };
+
+    let rest: Vec<_> = repeat(0.., (op(), supports_in_parens)).parse_next(input)?;
+    let Some((op, _)) = rest.first() else {
+        return Ok(first);
+    };
+
+    let is_and = op.eq_ignore_ascii_case("and");
+    if rest
+        .iter()
+        .any(|(x, _)| x.eq_ignore_ascii_case("and") != is_and)
+    {
+        return Err(winnow::error::ErrMode::Cut(()));
+    }
+
+    let tests = std::iter::once(first)
+        .chain(rest.into_iter().map(|(_, x)| x))
+        .collect();
+
+    Ok(if is_and {
+        SupportsCondition::And(tests)
+    } else {
+        SupportsCondition::Or(tests)
+    })
+}
+
+fn supports_in_parens<'a>(input: &mut &'a str) -> PResult<SupportsCondition<'a>, ()> {
+    alt((
+        delimited(('(', multispace0), supports_condition, (multispace0, ')')),
+        delimited('(', declaration, ')'),
+        delimited(
+            (tag_no_case("selector("), multispace0),
+            unpeek(Selector::parse),
+            (multispace0, ')'),
+        )
+        .map(|x| SupportsCondition::Selector(Box::new(x))),
+        unpeek(ComponentValue::parse)
+            .verify(|x| {
+                matches!(
+                    x,
+                    ComponentValue::Function(..) | ComponentValue::Block('(', _)
+                )
+            })
+            .recognize()
+            .map(SupportsCondition::Raw),
+    ))
+    .parse_next(input)
+}
+
+/// A `property: value` declaration, without its parentheses.
+fn declaration<'a>(input: &mut &'a str) -> PResult<SupportsCondition<'a>, ()> {
+    let property = delimited(multispace0, parse_ident, (multispace0, ':')).parse_next(input)?;
+    let value = repeat::<_, _, (), _, _>(1.., unpeek(ComponentValue::parse).void())
+        .recognize()
+        .map(str::trim)
+        .verify(|x: &str| !x.is_empty())
+        .parse_next(input)?;
+
+    Ok(SupportsCondition::Declaration(property, value))
+}
+
+fn page_selector<'a>(input: &mut &'a str) -> PResult<PageSelector<'a>, ()> {
+    let (name, pseudo) = (opt(parse_ident), repeat(0.., preceded(':', parse_ident)))
+        .verify(|(name, pseudo): &(Option<&str>, Vec<&str>)| name.is_some() || !pseudo.is_empty())
+        .parse_next(input)?;
+
+    Ok(PageSelector { name, pseudo })
+}
+
+/// A dotted layer name, e.g. `framework.base`.
+fn layer_name<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    (
+        parse_ident,
+        repeat::<_, _, (), _, _>(0.., ('.', parse_ident)),
+    )
+        .recognize()
+        .parse_next(input)
+}
+
+/// The optional name and condition of a `@container`.
+fn container<'a>(input: &mut &'a str) -> PResult<(Option<&'a str>, &'a str), ()> {
+    let name = opt(terminated(
+        parse_ident.verify(|x: &str| {
+            !["not", "and", "or"]
+                .iter()
+                .any(|y| y.eq_ignore_ascii_case(x))
+        }),
+        multispace1,
+    ))
+    .parse_next(input)?;
+
+    let condition = repeat::<_, _, (), _, _>(1.., unpeek(ComponentValue::parse).void())
+        .recognize()
+        .parse_next(input)?;
+
+    Ok((name, condition))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::Span, render::RenderCss};
+
+    fn qual_rule<'a>(name: &'a str, prelude: &'a str) -> QualRule<'a> {
+        QualRule(name.into(), Some(prelude.into()), Span::default())
+    }
+
+    #[test]
+    fn test_import() {
+        assert_eq!(
+            qual_rule(
+                "import",
+                "url(\"theme.css\") layer(base.theme) supports(display: grid) screen, print"
+            )
+            .at_rule(),
+            AtRule::Import(Import {
+                url: ComponentValue::Function("url", vec![ComponentValue::String("\"theme.css\"")]),
+                layer: Some("base.theme"),
+                supports: Some(SupportsCondition::Declaration("display", "grid")),
+                media: vec![
+                    MediaQuery {
+                        media_type: Some("screen"),
+                        ..MediaQuery::default()
+                    },
+                    MediaQuery {
+                        media_type: Some("print"),
+                        ..MediaQuery::default()
+                    }
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_supports() {
+        assert_eq!(
+            qual_rule(
+                "supports",
+                "(display: grid) and (not (display: inline-grid)) and selector(a > b)"
+            )
+            .at_rule(),
+            AtRule::Supports(SupportsCondition::And(vec![
+                SupportsCondition::Declaration("display", "grid"),
+                SupportsCondition::Not(Box::new(SupportsCondition::Declaration(
+                    "display",
+                    "inline-grid"
+                ))),
+                SupportsCondition::Selector(Box::new(Selector::parse::<()>("a > b").unwrap().1)),
+            ]))
+        );
+
+        assert_eq!(
+            qual_rule("supports", "(a: b) and (c: d) or (e: f)").at_rule(),
+            AtRule::Unknown("supports", Some("(a: b) and (c: d) or (e: f)"))
+        );
+    }
+
+    #[test]
+    fn test_page() {
+        assert_eq!(
+            qual_rule("page", "cover:first, :left").at_rule(),
+            AtRule::Page(vec![
+                PageSelector {
+                    name: Some("cover"),
+                    pseudo: vec!["first"],
+                },
+                PageSelector {
+                    name: None,
+                    pseudo: vec!["left"],
+                },
+            ])
+        );
+
+        assert_eq!(
+            QualRule("top-left".into(), None, Span::default()).at_rule(),
+            AtRule::PageMargin(PageMargin::TopLeft)
+        );
+    }
+
+    #[test]
+    fn test_container() {
+        assert_eq!(
+            qual_rule("container", "sidebar (min-width: 400px)").at_rule(),
+            AtRule::Container(Some("sidebar"), "(min-width: 400px)")
+        );
+
+        assert_eq!(
+            qual_rule("container", "not (min-width: 400px)").at_rule(),
+            AtRule::Container(None, "not (min-width: 400px)")
+        );
+    }
+
+    #[test]
+    fn test_misc() {
+        assert_eq!(
+            qual_rule("charset", "\"utf-8\"").at_rule(),
+            AtRule::Charset("utf-8")
+        );
+        let media = qual_rule("MEDIA", "screen and (min-width: 10px), print");
+        let AtRule::Media(media) = media.at_rule() else {
+            panic!()
+        };
+
+        assert_eq!(
+            media.iter().map(|x| x.as_css_string()).collect::<Vec<_>>(),
+            vec!["screen and (min-width:10px)", "print"]
+        );
+        assert_eq!(
+            qual_rule("media", "screen and (min-width: 10px) or print").at_rule(),
+            AtRule::Unknown("media", Some("screen and (min-width: 10px) or print"))
+        );
+        assert_eq!(
+            qual_rule("-webkit-keyframes", "spin").at_rule(),
+            AtRule::Keyframes("spin")
+        );
+        assert_eq!(
+            qual_rule("property", "--angle").at_rule(),
+            AtRule::Property("--angle")
+        );
+        assert_eq!(
+            qual_rule("blue", ": #00f").at_rule(),
+            AtRule::Variable("blue", "#00f")
+        );
+        assert_eq!(
+            qual_rule("page", ":first").at_rule(),
+            AtRule::Page(vec![PageSelector {
+                name: None,
+                pseudo: vec!["first"],
+            }])
+        );
+        assert_eq!(
+            qual_rule("unknown", "a b").at_rule(),
+            AtRule::Unknown("unknown", Some("a b"))
+        );
+    }
+}
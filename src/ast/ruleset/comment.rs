@@ -0,0 +1,128 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use std::borrow::Cow;
+
+use winnow::{
+    token::{tag, take_until0},
+    IResult, Parser,
+};
+
+use crate::{
+    ast::Span,
+    owned::IntoOwned,
+    parser::{ParseCss, ParseCssError},
+    render::RenderCss,
+    transform::TransformCss,
+};
+
+/// A `/* */` comment between rules or rulesets.  Comments are only rendered
+/// if `preserve` is set, which is the default for "important" comments of the
+/// form `/*! ... */` (e.g. license banners).  Comments elsewhere, such as
+/// within selectors or values, are discarded by the parser.
+///
+/// ```css
+/// /*! License: Apache-2.0 */
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comment<'a> {
+    /// The text of the comment, including the `/*` and `*/` delimiters.
+    pub text: Cow<'a, str>,
+    pub preserve: bool,
+    pub span: Span,
+}
+
+impl<'a> Comment<'a> {
+    /// Is this an "important" `/*! ... */` comment?
+    pub fn is_important(&self) -> bool {
+        self.text.starts_with("/*!")
+    }
+}
+
+impl<'a> ParseCss<'a> for Comment<'a> {
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        let (rest, text) = (tag("/*"), take_until0("*/"), tag("*/"))
+            .recognize()
+            .parse_peek(input)?;
+
+        Ok((rest, Comment {
+            text: text.into(),
+            preserve: text.starts_with("/*!"),
+            span: Span::parsed(input, rest),
+        }))
+    }
+}
+
+impl<'a> RenderCss for Comment<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.preserve {
+            write!(f, "{}", self.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> IntoOwned for Comment<'a> {
+    type Owned = Comment<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        Comment {
+            text: self.text.into_owned().into(),
+            preserve: self.preserve,
+            span: self.span,
+        }
+    }
+}
+
+impl<'a> TransformCss<Comment<'a>> for Comment<'a> {
+    fn transform_each<F: FnMut(&mut Comment<'a>)>(&mut self, f: &mut F) {
+        f(self)
+    }
+}
+
+impl<'a> TransformCss<Span> for Comment<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        f(&mut self.span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn test_important_comment() {
+        assert_matches!(
+            Comment::parse::<()>("/*! License */ div"),
+            Ok((" div", Comment {
+                text,
+                preserve: true,
+                ..
+            })) if text == "/*! License */"
+        )
+    }
+
+    #[test]
+    fn test_comment() {
+        assert_matches!(
+            Comment::parse::<()>("/* test; test */"),
+            Ok(("", Comment {
+                text,
+                preserve: false,
+                ..
+            })) if text == "/* test; test */"
+        )
+    }
+}
@@ -0,0 +1,295 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use std::borrow::Cow;
+
+use winnow::{
+    ascii::multispace0,
+    combinator::{alt, cut_err, delimited, peek, repeat, separated, terminated},
+    error::AddContext,
+    token::tag,
+    unpeek, IResult, Parser,
+};
+
+use super::{AtRule, Comment, QualRule, Rule};
+use crate::{
+    ast::{
+        flat_ruleset::FlatRule,
+        token::{comment0, parse_ident, sep0, trim_whitespace, NeedsWhitespaceStringExt},
+        ComponentValue, Span,
+    },
+    owned::IntoOwned,
+    parser::{ParseCss, ParseCssError},
+    render::RenderCss,
+    transform::TransformCss,
+};
+
+/// An `@keyframes` block.  Unlike other at-rules, its frames are not
+/// selectors, so they are never joined with the selector of a ruleset the
+/// `@keyframes` is nested in, nor merged by [`crate::transformers::dedupe`].
+///
+/// ```css
+/// @keyframes spin {
+///     from {
+///         transform: rotate(0deg);
+///     }
+///     50%, 75% {
+///         opacity: 0.5;
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyframesRuleset<'a>(pub QualRule<'a>, pub Vec<Keyframe<'a>>, pub Span);
+
+impl<'a> KeyframesRuleset<'a> {
+    /// The animation name of this `@keyframes`, e.g. `spin`.
+    pub fn name(&self) -> Option<&str> {
+        match self.0.at_rule() {
+            AtRule::Keyframes(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> RenderCss for KeyframesRuleset<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "@{}", self.0 .0)?;
+        if let Some(val) = &self.0 .1 {
+            if val.needs_pre_ws() {
+                write!(f, " ")?;
+            }
+
+            trim_whitespace(val, f);
+        }
+
+        write!(f, "{{")?;
+        self.1.render(f)?;
+        write!(f, "}}")
+    }
+}
+
+impl<'a> IntoOwned for KeyframesRuleset<'a> {
+    type Owned = KeyframesRuleset<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        KeyframesRuleset(self.0.into_owned(), self.1.into_owned(), self.2)
+    }
+}
+
+impl<'a> TransformCss<Rule<'a>> for KeyframesRuleset<'a> {
+    fn transform_each<F: FnMut(&mut Rule<'a>)>(&mut self, f: &mut F) {
+        for keyframe in self.1.iter_mut() {
+            for rule in keyframe.1.iter_mut() {
+                rule.transform_each(f);
+            }
+        }
+    }
+}
+
+impl<'a> TransformCss<Comment<'a>> for KeyframesRuleset<'a> {
+    fn transform_each<F: FnMut(&mut Comment<'a>)>(&mut self, f: &mut F) {
+        for keyframe in self.1.iter_mut() {
+            for rule in keyframe.1.iter_mut() {
+                rule.transform_each(f);
+            }
+        }
+    }
+}
+
+impl<'a> TransformCss<Span> for KeyframesRuleset<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        f(&mut self.2);
+        self.0.transform_each(f);
+        for keyframe in self.1.iter_mut() {
+            f(&mut keyframe.2);
+            for rule in keyframe.1.iter_mut() {
+                rule.transform_each(f);
+            }
+        }
+    }
+}
+
+/// A frame of a [`KeyframesRuleset`], by its comma-separated selectors and
+/// declarations.
+///
+/// ```css
+/// 50%, 75% {
+///     opacity: 0.5;
+/// }
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyframe<'a>(
+    pub Vec<KeyframeSelector<'a>>,
+    pub Vec<FlatRule<'a>>,
+    pub Span,
+);
+
+impl<'a> ParseCss<'a> for Keyframe<'a> {
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        let (rest, selectors) = separated(
+            1..,
+            delimited(multispace0, unpeek(KeyframeSelector::parse), multispace0),
+            ',',
+        )
+        .parse_peek(input)?;
+
+        let (rest, _) = (comment0, tag("{"), sep0).parse_peek(rest)?;
+        let comment = terminated(unpeek(Comment::parse), sep0).map(FlatRule::Comment);
+        let rule = unpeek(|input| -> IResult<&'a str, _, E> {
+            let (rest, rule) = Rule::parse(input)?;
+            let (rest, _) = cut_err(peek(alt((";", "}"))))
+                .parse_peek(rest)
+                .map_err(|err| err.add_context(&input, "`;` or `}` after declaration"))?;
+
+            Ok((rest, FlatRule::Rule(rule)))
+        });
+
+        let (rest, rules) = repeat(0.., alt((comment, terminated(rule, sep0)))).parse_peek(rest)?;
+        let (rest, _) = cut_err((comment0, tag("}")))
+            .context("declaration or `}`")
+            .parse_peek(rest)?;
+
+        Ok((rest, Keyframe(selectors, rules, Span::parsed(input, rest))))
+    }
+}
+
+impl<'a> IntoOwned for Keyframe<'a> {
+    type Owned = Keyframe<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        Keyframe(self.0.into_owned(), self.1.into_owned(), self.2)
+    }
+}
+
+impl<'a> RenderCss for Keyframe<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, selector) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+
+            selector.render(f)?;
+        }
+
+        write!(f, "{{")?;
+        self.1.render(f)?;
+        write!(f, "}}")
+    }
+}
+
+/// A selector of a [`Keyframe`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyframeSelector<'a> {
+    /// `from`, equivalent to `0%`.
+    From,
+
+    /// `to`, equivalent to `100%`.
+    To,
+
+    /// A percentage (without the `%`), e.g. `12.5%`.
+    Percentage(Cow<'a, str>),
+}
+
+impl<'a> KeyframeSelector<'a> {
+    /// The position of this frame in the animation, from `0.0` to `100.0`.
+    pub fn percentage(&self) -> Option<f64> {
+        match self {
+            KeyframeSelector::From => Some(0.0),
+            KeyframeSelector::To => Some(100.0),
+            KeyframeSelector::Percentage(x) => x.parse().ok(),
+        }
+    }
+}
+
+impl<'a> IntoOwned for KeyframeSelector<'a> {
+    type Owned = KeyframeSelector<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            KeyframeSelector::From => KeyframeSelector::From,
+            KeyframeSelector::To => KeyframeSelector::To,
+            KeyframeSelector::Percentage(x) => KeyframeSelector::Percentage(x.into_owned().into()),
+        }
+    }
+}
+
+impl<'a> ParseCss<'a> for KeyframeSelector<'a> {
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        alt((
+            parse_ident.verify_map(|x: &str| {
+                if x.eq_ignore_ascii_case("from") {
+                    Some(KeyframeSelector::From)
+                } else if x.eq_ignore_ascii_case("to") {
+                    Some(KeyframeSelector::To)
+                } else {
+                    None
+                }
+            }),
+            unpeek(ComponentValue::parse).verify_map(|x| match x {
+                ComponentValue::Percentage(x) => Some(KeyframeSelector::Percentage(x.into())),
+                _ => None,
+            }),
+        ))
+        .parse_peek(input)
+    }
+}
+
+impl<'a> RenderCss for KeyframeSelector<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyframeSelector::From => write!(f, "from"),
+            KeyframeSelector::To => write!(f, "to"),
+            KeyframeSelector::Percentage(x) => write!(f, "{}%", x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyframe_selectors() {
+        let (rest, keyframe) =
+            Keyframe::parse::<()>("FROM, 12.5% ,to { opacity: 0; /*! x */ }").unwrap();
+
+        assert_eq!(rest, "");
+        assert_eq!(keyframe.0, vec![
+            KeyframeSelector::From,
+            KeyframeSelector::Percentage("12.5".into()),
+            KeyframeSelector::To
+        ]);
+
+        assert_eq!(
+            keyframe
+                .0
+                .iter()
+                .map(|x| x.percentage())
+                .collect::<Vec<_>>(),
+            vec![Some(0.0), Some(12.5), Some(100.0)]
+        );
+
+        assert_eq!(
+            keyframe.as_css_string(),
+            "from,12.5%,to{opacity:0;/*! x */}"
+        );
+    }
+
+    #[test]
+    fn test_keyframe_invalid() {
+        assert!(Keyframe::parse::<()>("50 { opacity: 0; }").is_err());
+        assert!(Keyframe::parse::<()>("div { opacity: 0; }").is_err());
+        assert!(Keyframe::parse::<()>("50% { div { opacity: 0; } }").is_err());
+    }
+}
@@ -0,0 +1,686 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use std::collections::HashMap;
+
+use winnow::{
+    ascii::{multispace0, multispace1},
+    combinator::{alt, cut_err, delimited, opt, preceded, repeat, separated, terminated},
+    error::{ErrMode, ErrorKind, ParserError},
+    token::tag_no_case,
+    unpeek, IResult, PResult, Parser,
+};
+
+use crate::{
+    ast::{token::parse_ident, ComponentValue},
+    parser::{ParseCss, ParseCssError},
+    render::RenderCss,
+};
+
+/// A media query, per
+/// [Media Queries Level 4](https://www.w3.org/TR/mediaqueries-4/#mq-syntax).
+///
+/// ```
+/// use procss::{ast::*, RenderCss};
+/// let tree =
+///     procss::parse("@media only screen and (width >= 600px) { div { color: red; } }").unwrap();
+///
+/// let Ruleset::QualRuleset(QualRuleset(qual_rule, ..)) = &tree.0[0] else {
+///     panic!()
+/// };
+///
+/// let AtRule::Media(queries) = qual_rule.at_rule() else {
+///     panic!()
+/// };
+///
+/// assert_eq!(queries[0].media_type, Some("screen"));
+/// assert_eq!(
+///     queries[0].condition,
+///     Some(MediaCondition::Feature(MediaFeature::Range("width", vec![
+///         (MediaRangeOp::Ge, "600px")
+///     ])))
+/// );
+///
+/// assert_eq!(queries[0].as_css_string(), "only screen and (width>=600px)");
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct MediaQuery<'a> {
+    /// The leading `not` or `only`, which requires a media type.
+    pub modifier: Option<MediaModifier>,
+
+    /// The media type, e.g. `screen`, `print` or `all`.
+    pub media_type: Option<&'a str>,
+
+    /// The condition after the media type (and `and`), or the whole query
+    /// when there is no media type.
+    pub condition: Option<MediaCondition<'a>>,
+}
+
+/// The `not` or `only` before a [`MediaQuery`]'s media type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum MediaModifier {
+    Not,
+    Only,
+}
+
+/// A boolean combination of media features.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MediaCondition<'a> {
+    Feature(MediaFeature<'a>),
+    Not(Box<MediaCondition<'a>>),
+    And(Vec<MediaCondition<'a>>),
+    Or(Vec<MediaCondition<'a>>),
+}
+
+/// A parenthesized media feature test.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MediaFeature<'a> {
+    /// `(color)`
+    Boolean(&'a str),
+
+    /// `(min-width: 600px)`, by its name and value.
+    Plain(&'a str, &'a str),
+
+    /// `(width >= 600px)` or `(400px < width <= 700px)`, by its name and its
+    /// comparisons, which are always relative to the name, e.g. `(400px <
+    /// width)` is `("width", [(Gt, "400px")])`.
+    Range(&'a str, Vec<(MediaRangeOp, &'a str)>),
+}
+
+/// A comparison of a [`MediaFeature::Range`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum MediaRangeOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl MediaRangeOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaRangeOp::Lt => "<",
+            MediaRangeOp::Le => "<=",
+            MediaRangeOp::Gt => ">",
+            MediaRangeOp::Ge => ">=",
+            MediaRangeOp::Eq => "=",
+        }
+    }
+
+    /// The same comparison with its operands swapped, e.g. `<` for `>`.
+    fn flip(self) -> Self {
+        match self {
+            MediaRangeOp::Lt => MediaRangeOp::Gt,
+            MediaRangeOp::Le => MediaRangeOp::Ge,
+            MediaRangeOp::Gt => MediaRangeOp::Lt,
+            MediaRangeOp::Ge => MediaRangeOp::Le,
+            MediaRangeOp::Eq => MediaRangeOp::Eq,
+        }
+    }
+}
+
+impl<'a> MediaQuery<'a> {
+    /// The query `not all`, which never matches.
+    pub fn never() -> Self {
+        MediaQuery {
+            modifier: Some(MediaModifier::Not),
+            media_type: Some("all"),
+            condition: None,
+        }
+    }
+
+    /// Is this query `not all`, e.g. a [`MediaQuery::merge`] which can never
+    /// match?
+    pub fn is_never(&self) -> bool {
+        self.modifier == Some(MediaModifier::Not)
+            && self
+                .media_type
+                .map(|x| x.eq_ignore_ascii_case("all"))
+                .unwrap_or_default()
+            && self.condition.is_none()
+    }
+
+    /// A single query which matches when both `self` and `other` match, as
+    /// for `@media` blocks nested in one another.  This is
+    /// [`MediaQuery::never`] when the combination can never match, e.g. for
+    /// different media types or conflicting ranges of the same feature, and
+    /// `None` when it can't be written as one query because either has a
+    /// `not` modifier.
+    pub fn merge(&self, other: &Self) -> Option<Self> {
+        if self.modifier == Some(MediaModifier::Not) || other.modifier == Some(MediaModifier::Not) {
+            return None;
+        }
+
+        let is_any = |x: &Option<&str>| x.map(|x| x.eq_ignore_ascii_case("all")).unwrap_or(true);
+        let media_type = match (self.media_type, other.media_type) {
+            (x, y) if is_any(&y) => x.or(y),
+            (x, y) if is_any(&x) => y,
+            (Some(x), Some(y)) if x.eq_ignore_ascii_case(y) => Some(x),
+            _ => return Some(MediaQuery::never()),
+        };
+
+        let mut conditions = vec![];
+        for condition in [&self.condition, &other.condition].into_iter().flatten() {
+            match condition {
+                MediaCondition::And(xs) => conditions.extend(xs.iter().cloned()),
+                x => conditions.push(x.clone()),
+            }
+        }
+
+        let condition = match conditions.len() {
+            0 => None,
+            1 => conditions.pop(),
+            _ => Some(MediaCondition::And(conditions)),
+        };
+
+        if condition.as_ref().map(is_impossible).unwrap_or_default() {
+            return Some(MediaQuery::never());
+        }
+
+        let modifier = [self.modifier, other.modifier]
+            .into_iter()
+            .flatten()
+            .find(|_| media_type.is_some());
+
+        Some(MediaQuery {
+            modifier,
+            media_type,
+            condition,
+        })
+    }
+}
+
+/// A numeric bound on a feature, and whether it is inclusive.
+type Bound = (f64, bool);
+
+/// Do the features of this condition's top-level `and` contradict each
+/// other?  Only ranges of the same feature and unit, and different keyword
+/// values of the same feature, are compared.
+fn is_impossible(condition: &MediaCondition<'_>) -> bool {
+    let features: Vec<&MediaCondition<'_>> = match condition {
+        MediaCondition::And(xs) => xs.iter().collect(),
+        x => vec![x],
+    };
+
+    let mut ranges: HashMap<(String, String), (Option<Bound>, Option<Bound>)> = HashMap::new();
+    let mut keywords: HashMap<String, &str> = HashMap::new();
+    for feature in features {
+        let MediaCondition::Feature(feature) = feature else {
+            continue;
+        };
+
+        let comparisons = match feature {
+            MediaFeature::Boolean(_) => continue,
+            MediaFeature::Range(name, xs) => (name.to_ascii_lowercase(), xs.clone()),
+            MediaFeature::Plain(name, value) => {
+                let name = name.to_ascii_lowercase();
+                if let Some(name) = name.strip_prefix("min-") {
+                    (name.to_owned(), vec![(MediaRangeOp::Ge, *value)])
+                } else if let Some(name) = name.strip_prefix("max-") {
+                    (name.to_owned(), vec![(MediaRangeOp::Le, *value)])
+                } else if numeric(value).is_some() {
+                    (name, vec![(MediaRangeOp::Eq, *value)])
+                } else {
+                    if keywords
+                        .insert(name, *value)
+                        .map(|x| !x.eq_ignore_ascii_case(value))
+                        .unwrap_or_default()
+                    {
+                        return true;
+                    }
+
+                    continue;
+                }
+            }
+        };
+
+        let (name, comparisons) = comparisons;
+        for (op, value) in comparisons {
+            let Some((value, unit)) = numeric(value) else {
+                continue;
+            };
+
+            let (lower, upper) = ranges.entry((name.clone(), unit)).or_default();
+            if matches!(op, MediaRangeOp::Gt | MediaRangeOp::Ge | MediaRangeOp::Eq) {
+                let bound = (value, op != MediaRangeOp::Gt);
+                *lower = Some(match *lower {
+                    Some(x) if x.0 > bound.0 || (x.0 == bound.0 && !x.1) => x,
+                    _ => bound,
+                });
+            }
+
+            if matches!(op, MediaRangeOp::Lt | MediaRangeOp::Le | MediaRangeOp::Eq) {
+                let bound = (value, op != MediaRangeOp::Lt);
+                *upper = Some(match *upper {
+                    Some(x) if x.0 < bound.0 || (x.0 == bound.0 && !x.1) => x,
+                    _ => bound,
+                });
+            }
+        }
+    }
+
+    ranges.values().any(|x| match x {
+        (Some(lower), Some(upper)) => {
+            lower.0 > upper.0 || (lower.0 == upper.0 && !(lower.1 && upper.1))
+        }
+        _ => false,
+    })
+}
+
+/// The number and (lowercase) unit of a single numeric value.
+fn numeric(value: &str) -> Option<(f64, String)> {
+    match ComponentValue::parse_list(value).as_slice() {
+        [ComponentValue::Number(x)] => Some((x.parse().ok()?, String::new())),
+        [ComponentValue::Percentage(x)] => Some((x.parse().ok()?, "%".to_owned())),
+        [ComponentValue::Dimension(x, unit)] => Some((x.parse().ok()?, unit.to_ascii_lowercase())),
+        _ => None,
+    }
+}
+
+impl<'a> ParseCss<'a> for MediaQuery<'a> {
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        let condition = media_condition(true).map(|condition| MediaQuery {
+            condition: Some(condition),
+            ..MediaQuery::default()
+        });
+
+        let modifier = alt((
+            tag_no_case("not").value(MediaModifier::Not),
+            tag_no_case("only").value(MediaModifier::Only),
+        ));
+
+        let typed = (
+            opt(terminated(modifier, multispace1)),
+            media_type,
+            opt(preceded(
+                (multispace1, tag_no_case("and"), multispace0),
+                media_condition(false),
+            )),
+        )
+            .map(|(modifier, media_type, condition)| MediaQuery {
+                modifier,
+                media_type: Some(media_type),
+                condition,
+            });
+
+        alt((condition, typed)).parse_peek(input)
+    }
+}
+
+/// A comma-separated list of [`MediaQuery`], e.g. the prelude of `@media`.
+pub(super) fn media_query_list<'a, E: ParseCssError<'a>>(
+    input: &mut &'a str,
+) -> PResult<Vec<MediaQuery<'a>>, E> {
+    separated(
+        0..,
+        delimited(multispace0, unpeek(MediaQuery::parse), multispace0),
+        ',',
+    )
+    .parse_next(input)
+}
+
+fn media_type<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    parse_ident
+        .verify(|x: &str| {
+            !["not", "only", "and", "or", "layer"]
+                .iter()
+                .any(|y| y.eq_ignore_ascii_case(x))
+        })
+        .parse_next(input)
+}
+
+/// A `not`, or one or more tests joined by either `and` or `or` (the latter
+/// only when `allow_or`), which may not be mixed without parentheses.
+fn media_condition<'a, E: ParseCssError<'a>>(
+    allow_or: bool,
+) -> impl Parser<&'a str, MediaCondition<'a>, E> {
+    move |input: &mut &'a str| {
+        let not = preceded((tag_no_case("not"), multispace0), media_in_parens)
+            .map(|x| MediaCondition::Not(Box::new(x)));
+
+        alt((not, media_chain(allow_or))).parse_next(input)
+    }
+}
+
+fn media_chain<'a, E: ParseCssError<'a>>(
+    allow_or: bool,
+) -> impl Parser<&'a str, MediaCondition<'a>, E> {
+    move |input: &mut &'a str| {
+        let first = media_in_parens.parse_next(input)?;
+        let op = delimited(
+            multispace0,
+            alt((tag_no_case("and"), tag_no_case("or"))),
+            multispace0,
+        );
+
+        let rest: Vec<_> = repeat(0.., (op, media_in_parens)).parse_next(input)?;
+        let Some((op, _)) = rest.first() else {
+            return Ok(first);
+        };
+
+        let is_and = op.eq_ignore_ascii_case("and");
+        if (!is_and && !allow_or)
+            || rest
+                .iter()
+                .any(|(x, _)| x.eq_ignore_ascii_case("and") != is_and)
+        {
+            return Err(ErrMode::Cut(E::from_error_kind(input, ErrorKind::Verify)));
+        }
+
+        let tests = std::iter::once(first)
+            .chain(rest.into_iter().map(|(_, x)| x))
+            .collect();
+
+        Ok(if is_and {
+            MediaCondition::And(tests)
+        } else {
+            MediaCondition::Or(tests)
+        })
+    }
+}
+
+fn media_in_parens<'a, E: ParseCssError<'a>>(
+    input: &mut &'a str,
+) -> PResult<MediaCondition<'a>, E> {
+    delimited(
+        ('(', multispace0),
+        alt((
+            media_condition(true),
+            media_feature.map(MediaCondition::Feature),
+        )),
+        (multispace0, ')'),
+    )
+    .parse_next(input)
+}
+
+/// The contents of a [`MediaFeature`], without its parentheses.
+fn media_feature<'a, E: ParseCssError<'a>>(input: &mut &'a str) -> PResult<MediaFeature<'a>, E> {
+    let plain = (
+        terminated(parse_ident, (multispace0, ':', multispace0)),
+        cut_err(feature_value),
+    )
+        .map(|(name, value)| MediaFeature::Plain(name, value));
+
+    let name_first = (
+        terminated(parse_ident, multispace0),
+        range_op,
+        preceded(multispace0, feature_value),
+    )
+        .map(|(name, op, value)| MediaFeature::Range(name, vec![(op, value)]));
+
+    let value_first = (
+        terminated(feature_value, multispace0),
+        range_op,
+        delimited(multispace0, parse_ident, multispace0),
+        opt((range_op, preceded(multispace0, feature_value))),
+    )
+        .verify(|(_, op, _, second)| match second {
+            None => true,
+            Some((second, _)) => {
+                let is_lt = |x: &MediaRangeOp| matches!(x, MediaRangeOp::Lt | MediaRangeOp::Le);
+                let is_gt = |x: &MediaRangeOp| matches!(x, MediaRangeOp::Gt | MediaRangeOp::Ge);
+                (is_lt(op) && is_lt(second)) || (is_gt(op) && is_gt(second))
+            }
+        })
+        .map(|(value, op, name, second)| {
+            let comparisons = std::iter::once((op.flip(), value)).chain(second).collect();
+            MediaFeature::Range(name, comparisons)
+        });
+
+    alt((
+        plain,
+        name_first,
+        value_first,
+        parse_ident.map(MediaFeature::Boolean),
+    ))
+    .parse_next(input)
+}
+
+fn range_op<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<MediaRangeOp, E> {
+    alt((
+        "<=".value(MediaRangeOp::Le),
+        ">=".value(MediaRangeOp::Ge),
+        "<".value(MediaRangeOp::Lt),
+        ">".value(MediaRangeOp::Gt),
+        "=".value(MediaRangeOp::Eq),
+    ))
+    .parse_next(input)
+}
+
+/// A feature value, e.g. `600px` or `16 / 9`, up to the next comparison or
+/// `)`.
+fn feature_value<'a, E: ParseCssError<'a>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    fn token<'a, E: ParseCssError<'a>>(input: &mut &'a str) -> PResult<(), E> {
+        unpeek(ComponentValue::parse)
+            .verify(|x| {
+                !matches!(
+                    x,
+                    ComponentValue::Whitespace | ComponentValue::Delim('<' | '>' | '=' | ':' | ';')
+                )
+            })
+            .void()
+            .parse_next(input)
+    }
+
+    (token, repeat::<_, _, (), _, _>(0.., (multispace0, token)))
+        .recognize()
+        .parse_next(input)
+}
+
+impl<'a> RenderCss for MediaQuery<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.modifier {
+            Some(MediaModifier::Not) => write!(f, "not ")?,
+            Some(MediaModifier::Only) => write!(f, "only ")?,
+            None => (),
+        }
+
+        match (self.media_type, &self.condition) {
+            (Some(media_type), None) => write!(f, "{}", media_type),
+            (Some(media_type), Some(condition @ MediaCondition::Or(_))) => {
+                write!(f, "{} and (", media_type)?;
+                condition.render(f)?;
+                write!(f, ")")
+            }
+            (Some(media_type), Some(condition)) => {
+                write!(f, "{} and ", media_type)?;
+                condition.render(f)
+            }
+            (None, Some(condition)) => condition.render(f),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl<'a> MediaCondition<'a> {
+    fn render_in_parens(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MediaCondition::Feature(feature) => feature.render(f),
+            x => {
+                write!(f, "(")?;
+                x.render(f)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl<'a> RenderCss for MediaCondition<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (sep, tests) = match self {
+            MediaCondition::Feature(feature) => return feature.render(f),
+            MediaCondition::Not(x) => {
+                write!(f, "not ")?;
+                return x.render_in_parens(f);
+            }
+            MediaCondition::And(tests) => (" and ", tests),
+            MediaCondition::Or(tests) => (" or ", tests),
+        };
+
+        for (i, test) in tests.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", sep)?;
+            }
+
+            test.render_in_parens(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> RenderCss for MediaFeature<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MediaFeature::Boolean(name) => write!(f, "({})", name),
+            MediaFeature::Plain(name, value) => write!(f, "({}:{})", name, value),
+            MediaFeature::Range(name, comparisons) => match comparisons.as_slice() {
+                [(first, x), (second, y)] => {
+                    write!(
+                        f,
+                        "({}{}{}{}{})",
+                        x,
+                        first.flip().as_str(),
+                        name,
+                        second.as_str(),
+                        y
+                    )
+                }
+                xs => {
+                    write!(f, "({}", name)?;
+                    for (op, value) in xs {
+                        write!(f, "{}{}", op.as_str(), value)?;
+                    }
+
+                    write!(f, ")")
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(input: &str) -> MediaQuery<'_> {
+        MediaQuery::parse::<()>(input).unwrap().1
+    }
+
+    fn merge(x: &str, y: &str) -> Option<String> {
+        query(x).merge(&query(y)).map(|x| x.as_css_string())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(query("print"), MediaQuery {
+            media_type: Some("print"),
+            ..MediaQuery::default()
+        });
+
+        assert_eq!(query("not screen and (color)"), MediaQuery {
+            modifier: Some(MediaModifier::Not),
+            media_type: Some("screen"),
+            condition: Some(MediaCondition::Feature(MediaFeature::Boolean("color"))),
+        });
+
+        assert_eq!(
+            query("not (hover: hover)").condition,
+            Some(MediaCondition::Not(Box::new(MediaCondition::Feature(
+                MediaFeature::Plain("hover", "hover")
+            ))))
+        );
+
+        assert_eq!(
+            query("(min-width: 10px) or ((aspect-ratio: 16 / 9) and (color))").as_css_string(),
+            "(min-width:10px) or ((aspect-ratio:16 / 9) and (color))"
+        );
+
+        assert!(MediaQuery::parse::<()>("screen and (a) or (b)").is_err());
+        assert!(MediaQuery::parse::<()>("(a) and (b) or (c)").is_err());
+    }
+
+    #[test]
+    fn test_range() {
+        assert_eq!(
+            query("(400px < width <= 700px)").condition,
+            Some(MediaCondition::Feature(MediaFeature::Range("width", vec![
+                (MediaRangeOp::Gt, "400px"),
+                (MediaRangeOp::Le, "700px")
+            ])))
+        );
+
+        assert_eq!(
+            query("(100px >= height)").condition,
+            Some(MediaCondition::Feature(MediaFeature::Range(
+                "height",
+                vec![(MediaRangeOp::Le, "100px")]
+            )))
+        );
+
+        assert_eq!(
+            query("( 400px < width <= 700px )").as_css_string(),
+            "(400px<width<=700px)"
+        );
+
+        assert!(MediaQuery::parse::<()>("(400px < width > 700px)").is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        assert_eq!(
+            merge("screen", "(min-width: 10px)").as_deref(),
+            Some("screen and (min-width:10px)")
+        );
+
+        assert_eq!(
+            merge("only screen and (color)", "all and (min-width: 10px)").as_deref(),
+            Some("only screen and (color) and (min-width:10px)")
+        );
+
+        assert_eq!(
+            merge("screen", "(a) or (b)").as_deref(),
+            Some("screen and ((a) or (b))")
+        );
+
+        assert_eq!(merge("not print", "screen"), None);
+    }
+
+    #[test]
+    fn test_merge_never() {
+        assert_eq!(merge("screen", "print").as_deref(), Some("not all"));
+        assert_eq!(
+            merge("(max-width: 100px)", "(min-width: 200px)").as_deref(),
+            Some("not all")
+        );
+
+        assert_eq!(
+            merge("(width < 100px)", "(width >= 100px)").as_deref(),
+            Some("not all")
+        );
+
+        assert_eq!(
+            merge("(orientation: portrait)", "(orientation: landscape)").as_deref(),
+            Some("not all")
+        );
+
+        assert_eq!(
+            merge("(max-width: 100px)", "(min-width: 100px)").as_deref(),
+            Some("(max-width:100px) and (min-width:100px)")
+        );
+
+        assert_eq!(
+            merge("(max-width: 100em)", "(min-width: 200px)").as_deref(),
+            Some("(max-width:100em) and (min-width:200px)")
+        );
+    }
+}
@@ -9,8 +9,14 @@
 // │                                                                           │
 // └───────────────────────────────────────────────────────────────────────────┘
 
+mod at_rule;
+mod comment;
+mod keyframes;
+mod media;
 mod rule;
 
+use std::borrow::Cow;
+
 use winnow::{
     // branch::alt,
     combinator::{alt, repeat},
@@ -18,17 +24,31 @@ use winnow::{
     token::{tag, take_till1},
     // multi::many0,
     IResult,
+    PResult,
     Parser,
 };
 
-pub use self::rule::Rule;
+pub use self::{
+    at_rule::{AtRule, Import, PageMargin, PageSelector, SupportsCondition},
+    comment::Comment,
+    keyframes::{Keyframe, KeyframeSelector, KeyframesRuleset},
+    media::{MediaCondition, MediaFeature, MediaModifier, MediaQuery, MediaRangeOp},
+    rule::Rule,
+};
 use super::{
+    flat_ruleset::FlatRule,
     selector::{Selector, SelectorPath},
+    span::Span,
     token::{
-        comment0, parse_string_literal, parse_symbol, trim_whitespace, NeedsWhitespaceStringExt,
+        comment0, parse_ident, parse_string_literal, trim_whitespace, NeedsWhitespaceStringExt,
     },
 };
-use crate::{parser::ParseCss, render::*, transform::TransformCss};
+use crate::{
+    owned::IntoOwned,
+    parser::{ParseCss, ParseCssError},
+    render::*,
+    transform::TransformCss,
+};
 
 /// ```css
 /// div {
@@ -36,7 +56,34 @@ use crate::{parser::ParseCss, render::*, transform::TransformCss};
 /// }
 /// ```
 #[derive(Debug, Clone)]
-pub struct SelectorRuleset<'a, T>(pub Selector<'a>, pub Vec<T>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectorRuleset<'a, T>(pub Selector<'a>, pub Vec<T>, pub Span);
+
+impl<'a, T> SelectorRuleset<'a, T> {
+    /// Add a declaration `property: value` to this ruleset, which panics if
+    /// it is invalid (see [`Rule::new`]).
+    pub fn rule(self, property: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) -> Self
+    where
+        T: From<Rule<'a>>,
+    {
+        self.child(Rule::new(property, value))
+    }
+
+    /// Add a nested ruleset to this ruleset, e.g. one created with
+    /// [`Ruleset::selector`].
+    pub fn nest(self, ruleset: impl Into<Ruleset<'a, T>>) -> Self
+    where
+        T: From<Ruleset<'a, T>>,
+    {
+        self.child(ruleset.into())
+    }
+
+    /// Add a child node to this ruleset, e.g. a [`Rule`] or [`Comment`].
+    pub fn child(mut self, child: impl Into<T>) -> Self {
+        self.1.push(child.into());
+        self
+    }
+}
 
 impl<'a, T: RenderCss> RenderCss for SelectorRuleset<'a, T> {
     fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -47,46 +94,145 @@ impl<'a, T: RenderCss> RenderCss for SelectorRuleset<'a, T> {
     }
 }
 
-impl<'a> TransformCss<SelectorPath<'a>> for SelectorRuleset<'a, Rule<'a>> {
+impl<'a, T: IntoOwned> IntoOwned for SelectorRuleset<'a, T> {
+    type Owned = SelectorRuleset<'static, T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        SelectorRuleset(self.0.into_owned(), self.1.into_owned(), self.2)
+    }
+}
+
+impl<'a> TransformCss<SelectorPath<'a>> for SelectorRuleset<'a, FlatRule<'a>> {
     fn transform_each<F: FnMut(&mut SelectorPath<'a>)>(&mut self, f: &mut F) {
         self.0.transform_each(f)
     }
 }
 
+impl<'a, T: TransformCss<Span>> TransformCss<Span> for SelectorRuleset<'a, T> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        f(&mut self.2);
+        self.0.transform_each(f);
+        for rule in self.1.iter_mut() {
+            rule.transform_each(f);
+        }
+    }
+}
+
 /// ```css
 /// @import "test.css";
 /// ```
+///
+/// The prelude is usually borrowed from the source, but may be owned when it
+/// is generated by a transform, e.g. [`crate::transformers::merge_media`].
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub struct QualRule<'a>(pub &'a str, pub Option<&'a str>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QualRule<'a>(pub Cow<'a, str>, pub Option<Cow<'a, str>>, pub Span);
 
 impl<'a> ParseCss<'a> for QualRule<'a> {
-    fn parse<E: ParserError<&'a str>>(input: &'a str) -> IResult<&'a str, Self, E> {
-        let (input, _) = tag("@").parse_peek(input)?;
-        let (input, tagname) = parse_symbol.parse_peek(input)?;
-        let (input, _) = comment0.parse_peek(input)?;
-        let (input, property) = repeat::<_, _, Vec<_>, _, _>(
-            0..,
-            alt((take_till1(('\"', ';', '{', '}')), parse_string_literal())),
-        )
-        .recognize()
-        .parse_peek(input)?;
-
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        let (rest, _) = tag("@").parse_peek(input)?;
+        let (rest, tagname) = parse_ident.parse_peek(rest)?;
+        let (rest, _) = comment0.parse_peek(rest)?;
+        let (rest, property) = prelude.parse_peek(rest)?;
         let property = if property.is_empty() {
             None
         } else {
-            Some(property)
+            Some(property.into())
         };
 
-        Ok((input, QualRule(tagname, property)))
+        Ok((
+            rest,
+            QualRule(tagname.into(), property, Span::parsed(input, rest)),
+        ))
+    }
+}
+
+/// The prelude of an at-rule, which runs to the first `;`, `{` or `}` not in
+/// a string.
+fn prelude<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    repeat::<_, _, (), _, _>(
+        0..,
+        alt((
+            take_till1(('\"', '\'', ';', '{', '}')),
+            parse_string_literal(),
+        )),
+    )
+    .recognize()
+    .parse_next(input)
+}
+
+impl<'a> QualRule<'a> {
+    /// Create an at-rule `@name value`, e.g. `QualRule::new("import",
+    /// "\"x.css\"")`.  An empty `value` is omitted, e.g. for `@font-face`.
+    ///
+    /// # Panics
+    ///
+    /// If `name` is not an identifier, or `value` contains a `;`, `{` or `}`
+    /// outside of a string.  Use [`QualRule::try_new`] to handle the error
+    /// instead.
+    pub fn new(name: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) -> Self {
+        QualRule::try_new(name, value).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create an at-rule `@name value` as [`QualRule::new`] does, returning an
+    /// error if `name` or `value` is invalid rather than panicking.
+    ///
+    /// ```
+    /// use procss::ast::QualRule;
+    /// assert!(QualRule::try_new("media", "print").is_ok());
+    /// assert!(QualRule::try_new("media", "print{} body{color:red").is_err());
+    /// ```
+    pub fn try_new(
+        name: impl Into<Cow<'a, str>>,
+        value: impl Into<Cow<'a, str>>,
+    ) -> anyhow::Result<Self> {
+        let name = name.into();
+        let value = value.into();
+        if parse_ident::<()>.parse(&name).is_err() {
+            anyhow::bail!("Invalid at-rule name `{}`", name);
+        }
+
+        if prelude::<()>.parse(&value).is_err() {
+            anyhow::bail!("Invalid prelude `{}` for at-rule `@{}`", value, name);
+        }
+
+        let value = if value.is_empty() { None } else { Some(value) };
+        Ok(QualRule(name, value, Span::default()))
+    }
+
+    /// The prefix (if any) and URL of a `@namespace` declaration, e.g.
+    /// `(Some("svg"), "http://www.w3.org/2000/svg")` for
+    /// `@namespace svg url(http://www.w3.org/2000/svg);`.
+    pub fn namespace(&self) -> Option<(Option<&str>, &str)> {
+        match self.at_rule() {
+            AtRule::Namespace(prefix, url) => Some((prefix, url)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> IntoOwned for QualRule<'a> {
+    type Owned = QualRule<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        QualRule(self.0.into_owned().into(), self.1.into_owned(), self.2)
+    }
+}
+
+impl<'a> TransformCss<Span> for QualRule<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        f(&mut self.2)
     }
 }
 
 impl<'a> RenderCss for QualRule<'a> {
     fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "@")?;
-        trim_whitespace(self.0, f);
-        if let Some(val) = self.1 {
-            if val.needs_pre_ws() {
+        trim_whitespace(&self.0, f);
+        if let Some(val) = &self.1 {
+            // `@charset` is only recognized when written exactly as
+            // `@charset "...";`.
+            if val.needs_pre_ws() || self.0.eq_ignore_ascii_case("charset") {
                 write!(f, " ")?;
             }
 
@@ -103,13 +249,41 @@ impl<'a> RenderCss for QualRule<'a> {
 /// };
 /// ```
 #[derive(Debug, Clone)]
-pub struct QualRuleset<'a, T>(pub QualRule<'a>, pub Vec<T>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QualRuleset<'a, T>(pub QualRule<'a>, pub Vec<T>, pub Span);
+
+impl<'a, T> QualRuleset<'a, T> {
+    /// Add a declaration `property: value` to this at-rule's block, which
+    /// panics if it is invalid (see [`Rule::new`]).
+    pub fn rule(self, property: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) -> Self
+    where
+        T: From<Rule<'a>>,
+    {
+        self.child(Rule::new(property, value))
+    }
+
+    /// Add a nested ruleset to this at-rule's block, e.g. one created with
+    /// [`Ruleset::selector`].
+    pub fn nest(self, ruleset: impl Into<Ruleset<'a, T>>) -> Self
+    where
+        T: From<Ruleset<'a, T>>,
+    {
+        self.child(ruleset.into())
+    }
+
+    /// Add a child node to this at-rule's block, e.g. a [`Rule`] or
+    /// [`Comment`].
+    pub fn child(mut self, child: impl Into<T>) -> Self {
+        self.1.push(child.into());
+        self
+    }
+}
 
 impl<'a, T: RenderCss> RenderCss for QualRuleset<'a, T> {
     fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if !self.1.is_empty() {
             write!(f, "@{}", self.0 .0)?;
-            if let Some(val) = self.0 .1 {
+            if let Some(val) = &self.0 .1 {
                 if val.needs_pre_ws() {
                     write!(f, " ")?;
                 }
@@ -126,6 +300,14 @@ impl<'a, T: RenderCss> RenderCss for QualRuleset<'a, T> {
     }
 }
 
+impl<'a, T: IntoOwned> IntoOwned for QualRuleset<'a, T> {
+    type Owned = QualRuleset<'static, T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        QualRuleset(self.0.into_owned(), self.1.into_owned(), self.2)
+    }
+}
+
 impl<'a, T: TransformCss<U>, U> TransformCss<U> for QualRuleset<'a, T> {
     fn transform_each<F: FnMut(&mut U)>(&mut self, f: &mut F) {
         for ruleset in self.1.iter_mut() {
@@ -147,12 +329,13 @@ impl<'a, T: TransformCss<U>, U> TransformCss<U> for QualRuleset<'a, T> {
 /// }
 /// ```
 #[derive(Debug, Clone)]
-pub struct QualNestedRuleset<'a, T>(pub QualRule<'a>, pub Vec<Ruleset<'a, T>>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QualNestedRuleset<'a, T>(pub QualRule<'a>, pub Vec<Ruleset<'a, T>>, pub Span);
 
 impl<'a, T: RenderCss> RenderCss for QualNestedRuleset<'a, T> {
     fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if !self.1.is_empty() {
-            let QualNestedRuleset(QualRule(name, val), node) = self;
+            let QualNestedRuleset(QualRule(name, val, _), node, _) = self;
             write!(f, "@{}", name)?;
             if let Some(val) = val {
                 if val.needs_pre_ws() {
@@ -171,6 +354,14 @@ impl<'a, T: RenderCss> RenderCss for QualNestedRuleset<'a, T> {
     }
 }
 
+impl<'a, T: IntoOwned> IntoOwned for QualNestedRuleset<'a, T> {
+    type Owned = QualNestedRuleset<'static, T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        QualNestedRuleset(self.0.into_owned(), self.1.into_owned(), self.2)
+    }
+}
+
 impl<'a, T, U> TransformCss<U> for QualNestedRuleset<'a, T>
 where
     Ruleset<'a, T>: TransformCss<U>,
@@ -202,11 +393,143 @@ where
 /// ```
 #[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Ruleset<'a, T> {
     SelectorRuleset(SelectorRuleset<'a, T>),
     QualRule(QualRule<'a>),
     QualRuleset(QualRuleset<'a, T>),
     QualNestedRuleset(QualNestedRuleset<'a, T>),
+    Keyframes(KeyframesRuleset<'a>),
+    Comment(Comment<'a>),
+}
+
+impl<'a, T> Ruleset<'a, T> {
+    /// Create an empty [`SelectorRuleset`] for `selector`, which may be a
+    /// [`Selector`] or a selector list to parse, e.g. `"div > .open, span"`.
+    /// Add rules and nested rulesets to it with [`SelectorRuleset::rule`] and
+    /// [`SelectorRuleset::nest`].
+    ///
+    /// # Panics
+    ///
+    /// If `selector` is a string which is not a valid selector list.  Use
+    /// [`Ruleset::try_selector`] to handle the error instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use procss::{
+    ///     ast::{Ruleset, Tree},
+    ///     RenderCss,
+    /// };
+    /// let tree: Tree = [Ruleset::selector("div")
+    ///     .rule("color", "red")
+    ///     .nest(Ruleset::selector("&:hover").rule("color", "blue"))]
+    /// .into_iter()
+    /// .collect();
+    ///
+    /// let css = tree.flatten_tree().as_css_string();
+    /// assert_eq!(css, "div{color:red;}div:hover{color:blue;}");
+    /// ```
+    pub fn selector<S>(selector: S) -> SelectorRuleset<'a, T>
+    where
+        S: TryInto<Selector<'a>>,
+        S::Error: std::fmt::Display,
+    {
+        Ruleset::try_selector(selector).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create an empty [`SelectorRuleset`] as [`Ruleset::selector`] does,
+    /// returning an error if `selector` is not a valid selector list rather
+    /// than panicking.
+    ///
+    /// ```
+    /// use procss::ast::{Ruleset, TreeRuleset};
+    /// assert!(TreeRuleset::try_selector("div > .open").is_ok());
+    /// assert!(TreeRuleset::try_selector("div >").is_err());
+    /// ```
+    pub fn try_selector<S>(selector: S) -> Result<SelectorRuleset<'a, T>, S::Error>
+    where
+        S: TryInto<Selector<'a>>,
+    {
+        let selector = selector.try_into()?;
+        Ok(SelectorRuleset(selector, vec![], Span::default()))
+    }
+
+    /// Create an empty at-rule block `@name params {}`, e.g.
+    /// `Ruleset::at_rule("media", "(max-width: 600px)")`.  Add rules and nested
+    /// rulesets to it with [`QualRuleset::rule`] and [`QualRuleset::nest`].
+    /// For `@keyframes`, create a [`KeyframesRuleset`] instead.
+    ///
+    /// # Panics
+    ///
+    /// If `name` or `params` is invalid (see [`QualRule::new`]).  Use
+    /// [`Ruleset::try_at_rule`] to handle the error instead.
+    pub fn at_rule(
+        name: impl Into<Cow<'a, str>>,
+        params: impl Into<Cow<'a, str>>,
+    ) -> QualRuleset<'a, T> {
+        QualRuleset(QualRule::new(name, params), vec![], Span::default())
+    }
+
+    /// Create an empty at-rule block as [`Ruleset::at_rule`] does, returning
+    /// an error if `name` or `params` is invalid rather than panicking.
+    pub fn try_at_rule(
+        name: impl Into<Cow<'a, str>>,
+        params: impl Into<Cow<'a, str>>,
+    ) -> anyhow::Result<QualRuleset<'a, T>> {
+        let qual_rule = QualRule::try_new(name, params)?;
+        Ok(QualRuleset(qual_rule, vec![], Span::default()))
+    }
+
+    /// The [`Span`] of the source this ruleset was parsed from.  For rulesets
+    /// generated by [`crate::ast::Tree::flatten_tree`], this is the span of the
+    /// nested ruleset they were flattened from.
+    pub fn span(&self) -> Span {
+        match self {
+            Ruleset::SelectorRuleset(ruleset) => ruleset.2,
+            Ruleset::QualRule(rule) => rule.2,
+            Ruleset::QualRuleset(ruleset) => ruleset.2,
+            Ruleset::QualNestedRuleset(ruleset) => ruleset.2,
+            Ruleset::Keyframes(ruleset) => ruleset.2,
+            Ruleset::Comment(comment) => comment.span,
+        }
+    }
+}
+
+impl<'a, T> From<SelectorRuleset<'a, T>> for Ruleset<'a, T> {
+    fn from(ruleset: SelectorRuleset<'a, T>) -> Self {
+        Ruleset::SelectorRuleset(ruleset)
+    }
+}
+
+impl<'a, T> From<QualRule<'a>> for Ruleset<'a, T> {
+    fn from(rule: QualRule<'a>) -> Self {
+        Ruleset::QualRule(rule)
+    }
+}
+
+impl<'a, T> From<QualRuleset<'a, T>> for Ruleset<'a, T> {
+    fn from(ruleset: QualRuleset<'a, T>) -> Self {
+        Ruleset::QualRuleset(ruleset)
+    }
+}
+
+impl<'a, T> From<QualNestedRuleset<'a, T>> for Ruleset<'a, T> {
+    fn from(ruleset: QualNestedRuleset<'a, T>) -> Self {
+        Ruleset::QualNestedRuleset(ruleset)
+    }
+}
+
+impl<'a, T> From<KeyframesRuleset<'a>> for Ruleset<'a, T> {
+    fn from(ruleset: KeyframesRuleset<'a>) -> Self {
+        Ruleset::Keyframes(ruleset)
+    }
+}
+
+impl<'a, T> From<Comment<'a>> for Ruleset<'a, T> {
+    fn from(comment: Comment<'a>) -> Self {
+        Ruleset::Comment(comment)
+    }
 }
 
 impl<'a, T: RenderCss> RenderCss for Ruleset<'a, T> {
@@ -216,6 +539,23 @@ impl<'a, T: RenderCss> RenderCss for Ruleset<'a, T> {
             Ruleset::QualRule(rule) => rule.render(f),
             Ruleset::QualRuleset(ruleset) => ruleset.render(f),
             Ruleset::QualNestedRuleset(ruleset) => ruleset.render(f),
+            Ruleset::Keyframes(ruleset) => ruleset.render(f),
+            Ruleset::Comment(comment) => comment.render(f),
+        }
+    }
+}
+
+impl<'a, T: IntoOwned> IntoOwned for Ruleset<'a, T> {
+    type Owned = Ruleset<'static, T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            Ruleset::SelectorRuleset(x) => Ruleset::SelectorRuleset(x.into_owned()),
+            Ruleset::QualRule(x) => Ruleset::QualRule(x.into_owned()),
+            Ruleset::QualRuleset(x) => Ruleset::QualRuleset(x.into_owned()),
+            Ruleset::QualNestedRuleset(x) => Ruleset::QualNestedRuleset(x.into_owned()),
+            Ruleset::Keyframes(x) => Ruleset::Keyframes(x.into_owned()),
+            Ruleset::Comment(x) => Ruleset::Comment(x.into_owned()),
         }
     }
 }
@@ -224,9 +564,11 @@ impl<'a, T: TransformCss<Rule<'a>>> TransformCss<Rule<'a>> for Ruleset<'a, T> {
     fn transform_each<F: FnMut(&mut Rule<'a>)>(&mut self, f: &mut F) {
         match self {
             Ruleset::QualRule(_) => (),
+            Ruleset::Comment(_) => (),
             Ruleset::QualRuleset(rules) => rules.transform_each(f),
             Ruleset::QualNestedRuleset(ruleset) => ruleset.transform_each(f),
-            Ruleset::SelectorRuleset(SelectorRuleset(_, rules)) => {
+            Ruleset::Keyframes(ruleset) => ruleset.transform_each(f),
+            Ruleset::SelectorRuleset(SelectorRuleset(_, rules, _)) => {
                 for rule in rules.iter_mut() {
                     rule.transform_each(f);
                 }
@@ -234,3 +576,45 @@ impl<'a, T: TransformCss<Rule<'a>>> TransformCss<Rule<'a>> for Ruleset<'a, T> {
         }
     }
 }
+
+impl<'a, T: TransformCss<Comment<'a>>> TransformCss<Comment<'a>> for Ruleset<'a, T> {
+    fn transform_each<F: FnMut(&mut Comment<'a>)>(&mut self, f: &mut F) {
+        match self {
+            Ruleset::QualRule(_) => (),
+            Ruleset::Comment(comment) => f(comment),
+            Ruleset::QualRuleset(rules) => rules.transform_each(f),
+            Ruleset::QualNestedRuleset(ruleset) => ruleset.transform_each(f),
+            Ruleset::Keyframes(ruleset) => ruleset.transform_each(f),
+            Ruleset::SelectorRuleset(SelectorRuleset(_, rules, _)) => {
+                for rule in rules.iter_mut() {
+                    rule.transform_each(f);
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T: TransformCss<Span>> TransformCss<Span> for Ruleset<'a, T> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        match self {
+            Ruleset::SelectorRuleset(ruleset) => ruleset.transform_each(f),
+            Ruleset::QualRule(rule) => rule.transform_each(f),
+            Ruleset::Comment(comment) => comment.transform_each(f),
+            Ruleset::Keyframes(ruleset) => ruleset.transform_each(f),
+            Ruleset::QualRuleset(ruleset) => {
+                f(&mut ruleset.2);
+                ruleset.0.transform_each(f);
+                for rule in ruleset.1.iter_mut() {
+                    rule.transform_each(f);
+                }
+            }
+            Ruleset::QualNestedRuleset(ruleset) => {
+                f(&mut ruleset.2);
+                ruleset.0.transform_each(f);
+                for rule in ruleset.1.iter_mut() {
+                    rule.transform_each(f);
+                }
+            }
+        }
+    }
+}
@@ -12,23 +12,109 @@
 use std::borrow::Cow;
 
 use winnow::{
-    combinator::{alt, not, repeat},
-    error::ParserError,
-    token::{tag, take_till1},
-    IResult, Parser,
+    combinator::{alt, delimited, not, opt, repeat},
+    error::{AddContext, ParserError},
+    token::{tag, take_till1, take_until0},
+    IResult, PResult, Parser,
 };
 
 use crate::{
-    ast::token::{comment0, parse_string_literal, parse_symbol, trim_whitespace},
+    ast::{
+        token::{comment0, parse_ident, parse_string_literal, trim_whitespace},
+        ComponentValue, Span,
+    },
+    owned::IntoOwned,
     render::RenderCss,
     transform::TransformCss,
 };
 
 /// A CSS rule, of the form `xxx: yyy` (delimited by `;` in a ruleset).
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rule<'a> {
     pub property: Cow<'a, str>,
+
+    /// The value of this rule, without its `!important` flag.
     pub value: Cow<'a, str>,
+
+    /// Is this rule flagged `!important`?
+    pub important: bool,
+    pub span: Span,
+}
+
+impl<'a> Rule<'a> {
+    /// Create a declaration `property: value`, which is `!important` if
+    /// `value` ends with an `!important` flag.
+    ///
+    /// # Panics
+    ///
+    /// If `property` is not an identifier, or `value` is not a valid value,
+    /// e.g. if it contains a `;`, `{` or `}` outside of a string.  Use
+    /// [`Rule::try_new`] to handle the error instead.
+    pub fn new(property: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) -> Self {
+        Rule::try_new(property, value).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create a declaration `property: value` as [`Rule::new`] does, returning
+    /// an error if `property` or `value` is invalid rather than panicking.
+    ///
+    /// ```
+    /// use procss::ast::Rule;
+    /// let rule = Rule::try_new("color", "red !important").unwrap();
+    /// assert_eq!((&*rule.value, rule.important), ("red", true));
+    /// assert!(Rule::try_new("color", "red; } body { x: y").is_err());
+    /// ```
+    pub fn try_new(
+        property: impl Into<Cow<'a, str>>,
+        value: impl Into<Cow<'a, str>>,
+    ) -> anyhow::Result<Self> {
+        let property = property.into();
+        let value = value.into();
+        if parse_ident::<()>.parse(&property).is_err() {
+            anyhow::bail!("Invalid property `{}`", property);
+        }
+
+        let valid = if property.starts_with("--") {
+            custom_value::<()>.parse(&value).is_ok()
+        } else {
+            repeat::<_, _, (), _, _>(
+                0..,
+                alt((
+                    take_till1(('\"', '\'', ';', '{', '}')).void(),
+                    parse_string_literal::<()>().void(),
+                )),
+            )
+            .parse(&value)
+            .is_ok()
+        };
+
+        if !valid {
+            anyhow::bail!("Invalid value `{}` for property `{}`", value, property);
+        }
+
+        let (rest, important) = split_important(&value);
+        let len = rest.len();
+
+        let value = match value {
+            Cow::Borrowed(value) => Cow::Borrowed(&value[..len]),
+            Cow::Owned(mut value) => {
+                value.truncate(len);
+                Cow::Owned(value)
+            }
+        };
+
+        Ok(Rule {
+            property,
+            value,
+            important,
+            span: Span::default(),
+        })
+    }
+
+    /// Parse this rule's `value` into a list of [`ComponentValue`].
+    pub fn values(&self) -> Vec<ComponentValue<'_>> {
+        ComponentValue::parse_list(&self.value)
+    }
 }
 
 impl<'a> TransformCss<Rule<'a>> for Rule<'a> {
@@ -37,11 +123,43 @@ impl<'a> TransformCss<Rule<'a>> for Rule<'a> {
     }
 }
 
+impl<'a> IntoOwned for Rule<'a> {
+    type Owned = Rule<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        Rule {
+            property: self.property.into_owned().into(),
+            value: self.value.into_owned().into(),
+            important: self.important,
+            span: self.span,
+        }
+    }
+}
+
+impl<'a> TransformCss<Span> for Rule<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        f(&mut self.span)
+    }
+}
+
 impl<'a> RenderCss for Rule<'a> {
     fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let Rule { property, value } = self;
+        let Rule {
+            property, value, ..
+        } = self;
         write!(f, "{}:", property)?;
-        trim_whitespace(value, f);
+        if property.starts_with("--") {
+            // Custom property values are token sequences which may be read
+            // back as written, so only their outer whitespace is trimmed.
+            write!(f, "{}", value.trim())?;
+        } else {
+            trim_whitespace(value, f);
+        }
+
+        if self.important {
+            write!(f, "!important")?;
+        }
+
         write!(f, ";")
     }
 }
@@ -49,22 +167,85 @@ impl<'a> RenderCss for Rule<'a> {
 // TODO property is not the same parser as tag.
 // TODO this Cow is not borrowed ...
 impl<'a> crate::parser::ParseCss<'a> for Rule<'a> {
-    fn parse<E: ParserError<&'a str>>(input: &'a str) -> IResult<&'a str, Self, E> {
-        let (input, property) = parse_symbol.parse_peek(input)?;
-        let (input, _) = Parser::parse_peek(&mut (comment0, tag(":"), comment0), input)?;
-        let (input, value) = repeat::<_, _, Vec<_>, _, _>(
-            0..,
-            alt((take_till1(('\"', ';', '}')), parse_string_literal())),
-        )
-        .recognize()
-        .parse_peek(input)?;
-        Ok((input, Rule {
+    fn parse<E: crate::parser::ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        let (rest, property) = parse_ident.parse_peek(input)?;
+        let (rest, _) = Parser::parse_peek(&mut (comment0, tag(":"), comment0), rest)
+            .map_err(|err| err.add_context(&input, "`:` after property"))?;
+        let (rest, value) = if property.starts_with("--") {
+            custom_value.parse_peek(rest)?
+        } else {
+            repeat::<_, _, Vec<_>, _, _>(
+                0..,
+                alt((take_till1(('\"', '\'', ';', '}')), parse_string_literal())),
+            )
+            .recognize()
+            .parse_peek(rest)?
+        };
+
+        let (value, important) = split_important(value);
+        Ok((rest, Rule {
             property: property.into(),
             value: value.into(),
+            important,
+            span: Span::parsed(input, rest),
         }))
     }
 }
 
+/// The value of a custom property, which runs to the first `;` or `}` not
+/// nested in a `()`, `[]` or `{}` block, string or comment.
+fn custom_value<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    repeat::<_, _, (), _, _>(
+        0..,
+        alt((
+            take_till1(('\"', '\'', '/', ';', '(', ')', '[', ']', '{', '}')).void(),
+            balanced,
+        )),
+    )
+    .recognize()
+    .parse_next(input)
+}
+
+/// A string, comment or `()`, `[]` or `{}` block with balanced contents.
+fn balanced<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<(), E> {
+    alt((
+        parse_string_literal().void(),
+        ("/*", take_until0("*/"), opt("*/")).void(),
+        '/'.void(),
+        delimited('(', block_contents, ')'),
+        delimited('[', block_contents, ']'),
+        delimited('{', block_contents, '}'),
+    ))
+    .parse_next(input)
+}
+
+fn block_contents<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<(), E> {
+    repeat(
+        0..,
+        alt((
+            take_till1(('\"', '\'', '/', '(', ')', '[', ']', '{', '}')).void(),
+            balanced,
+        )),
+    )
+    .parse_next(input)
+}
+
+/// Split a trailing `!important` flag, which may have whitespace after the `!`
+/// and be in any case, from a rule's value.
+fn split_important(value: &str) -> (&str, bool) {
+    let trimmed = value.trim_end();
+    let flag = trimmed.len().checked_sub("important".len());
+    let rest = flag
+        .filter(|x| trimmed.is_char_boundary(*x))
+        .filter(|x| trimmed[*x..].eq_ignore_ascii_case("important"))
+        .and_then(|x| trimmed[..x].trim_end().strip_suffix('!'));
+
+    match rest {
+        Some(rest) => (rest.trim_end(), true),
+        None => (value, false),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::assert_matches::assert_matches;
@@ -79,6 +260,7 @@ mod tests {
             Ok(("", Rule {
                 property,
                 value,
+                ..
             })) if value == "url(\"test\")" && property == "--column-selector--background"
         )
     }
@@ -90,6 +272,7 @@ mod tests {
             Ok(("", Rule {
                 property,
                 value,
+                ..
             })) if value == "\"\\1234\"" && property == "test"
         )
     }
@@ -101,11 +284,40 @@ mod tests {
             Ok(("", Rule {
                 property,
                 value,
+                ..
             })) if value == "\": test ; alpha\"" && property ==  "test"
         )
     }
 
-    #[ignore]
+    #[test]
+    fn test_rule_values() {
+        let (_, rule) = Rule::parse::<()>("border: 1px solid rgba(0, 0, 0, 0.5)").unwrap();
+        let values = rule.values();
+        assert_eq!(values.len(), 5);
+        assert_eq!(values[0], ComponentValue::Dimension("1", "px"));
+        assert_matches!(&values[4], ComponentValue::Function("rgba", args) if args.len() == 10);
+    }
+
+    #[test]
+    fn test_rule_important() {
+        assert_matches!(
+            Rule::parse::<()>("color: red ! IMPORTANT "),
+            Ok(("", Rule {
+                value,
+                important: true,
+                ..
+            })) if value == "red"
+        );
+
+        assert_matches!(
+            Rule::parse::<()>("content: \"!important\""),
+            Ok(("", Rule {
+                important: false,
+                ..
+            }))
+        );
+    }
+
     #[test]
     fn test_rule_escaped_string_3() {
         assert_matches!(
@@ -113,7 +325,44 @@ mod tests {
             Ok(("", Rule {
                 property,
                 value,
-            })) if value == "\"\\1234\"" && property == "test"
+                ..
+            })) if value == "': test ; alpha'" && property == "test"
         )
     }
+
+    #[test]
+    fn test_rule_render_string() {
+        let (_, rule) = Rule::parse::<()>("content: ': x ; y'   \"a  ,  b\" attr( x )").unwrap();
+        assert_eq!(rule.as_css_string(), "content:': x ; y'\"a  ,  b\"attr(x);");
+
+        let (_, rule) = Rule::parse::<()>("content: \"\\\" ; \\\\\" '  '").unwrap();
+        assert_eq!(rule.as_css_string(), "content:\"\\\" ; \\\\\"'  ';");
+    }
+
+    #[test]
+    fn test_rule_custom_property() {
+        assert_matches!(
+            Rule::parse::<()>("--x: { a: b; } ; y"),
+            Ok(("; y", Rule {
+                value,
+                ..
+            })) if value == "{ a: b; } "
+        );
+
+        assert_matches!(
+            Rule::parse::<()>("--json: [1;2] (\"}\" /* ; */) }"),
+            Ok(("}", Rule {
+                value,
+                ..
+            })) if value == "[1;2] (\"}\" /* ; */) "
+        );
+
+        assert_matches!(
+            Rule::parse::<()>("--x: (];"),
+            Ok(("(];", Rule {
+                value,
+                ..
+            })) if value.is_empty()
+        );
+    }
 }
@@ -9,43 +9,200 @@
 // │                                                                           │
 // └───────────────────────────────────────────────────────────────────────────┘
 
+use std::borrow::Cow;
+
 use winnow::{
-    combinator::{not, opt, preceded},
-    error::ParserError,
-    token::{tag, take_till1},
+    ascii::multispace0,
+    combinator::{alt, not, opt, terminated},
+    token::{one_of, tag, take_till1},
     IResult, Parser,
 };
 
-use crate::{ast::token::*, parser::*};
+use crate::{
+    ast::{token::*, Span},
+    owned::IntoOwned,
+    parser::*,
+    render::RenderCss,
+    transform::TransformCss,
+};
 
 /// A selector which matches attributes, optionally against their value as well.
-/// TODO doesn't support comma-separated multiple selectors.
 ///
 /// # Example
 ///
 /// ```css
 /// div[name=test] {}
-/// div[disabled,data-value="red"] {}
+/// div[disabled][data-value="red"] {}
+/// a[href^="http" i] {}
+/// svg[xlink|href] {}
 /// ```
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SelectorAttr<'a> {
-    pub name: &'a str,
-    pub value: Option<&'a str>,
+    /// The namespace prefix of `name`, e.g. `xlink` in `[xlink|href]`, which
+    /// is `*` for any namespace and `""` for no namespace (`[|href]`).
+    pub namespace: Option<Cow<'a, str>>,
+    pub name: Cow<'a, str>,
+    pub operator: Option<AttrOperator>,
+
+    /// The value to match, as written (including quotes, if any).
+    pub value: Option<Cow<'a, str>>,
+    pub flag: Option<AttrFlag>,
+    pub span: Span,
+}
+
+/// The operator an attribute's value is matched with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AttrOperator {
+    /// `=`
+    Equals,
+
+    /// `~=`, matches one of a whitespace-separated list of words.
+    Includes,
+
+    /// `|=`, matches exactly or followed by `-`.
+    DashMatch,
+
+    /// `^=`
+    Prefix,
+
+    /// `$=`
+    Suffix,
+
+    /// `*=`
+    Substring,
+}
+
+impl AttrOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttrOperator::Equals => "=",
+            AttrOperator::Includes => "~=",
+            AttrOperator::DashMatch => "|=",
+            AttrOperator::Prefix => "^=",
+            AttrOperator::Suffix => "$=",
+            AttrOperator::Substring => "*=",
+        }
+    }
+}
+
+/// The case-sensitivity flag of an attribute selector, e.g. `[type="a" i]`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AttrFlag {
+    /// `i`
+    Insensitive,
+
+    /// `s`
+    Sensitive,
+}
+
+fn parse_operator<'a, E: ParseCssError<'a>>(
+    input: &mut &'a str,
+) -> winnow::PResult<AttrOperator, E> {
+    alt((
+        tag("=").value(AttrOperator::Equals),
+        tag("~=").value(AttrOperator::Includes),
+        tag("|=").value(AttrOperator::DashMatch),
+        tag("^=").value(AttrOperator::Prefix),
+        tag("$=").value(AttrOperator::Suffix),
+        tag("*=").value(AttrOperator::Substring),
+    ))
+    .parse_next(input)
 }
 
 impl<'a> ParseCss<'a> for SelectorAttr<'a> {
     fn parse<E>(input: &'a str) -> IResult<&'a str, Self, E>
     where
-        E: ParserError<&'a str>,
+        E: ParseCssError<'a>,
     {
-        let (rest, (_, name, value, _)) = (
+        let namespace = terminated(
+            alt((parse_ident, tag("*"), tag(""))),
+            (tag("|"), not(tag("="))),
+        );
+
+        let value = alt((
+            parse_string_literal(),
+            take_till1(|x: char| x.is_whitespace() || matches!(x, ']' | '"' | '\'')),
+        ));
+
+        let flag = one_of(['i', 'I', 's', 'S']).map(|x: char| match x {
+            'i' | 'I' => AttrFlag::Insensitive,
+            _ => AttrFlag::Sensitive,
+        });
+
+        let (rest, (_, _, namespace, name, _, matcher, _)) = (
             tag("["),
-            parse_symbol,
-            opt(preceded(tag("="), take_till1(']'))),
+            multispace0,
+            opt(namespace),
+            parse_ident,
+            multispace0,
+            opt((
+                parse_operator,
+                multispace0,
+                value,
+                multispace0,
+                opt(terminated(flag, multispace0)),
+            )),
             tag("]"),
         )
             .parse_peek(input)?;
-        Ok((rest, SelectorAttr { name, value }))
+
+        let (operator, value, flag) = match matcher {
+            Some((operator, _, value, _, flag)) => (Some(operator), Some(value.into()), flag),
+            None => (None, None, None),
+        };
+
+        Ok((rest, SelectorAttr {
+            namespace: namespace.map(Cow::Borrowed),
+            name: name.into(),
+            operator,
+            value,
+            flag,
+            span: Span::parsed(input, rest),
+        }))
+    }
+}
+
+impl<'a> IntoOwned for SelectorAttr<'a> {
+    type Owned = SelectorAttr<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        SelectorAttr {
+            namespace: self.namespace.into_owned(),
+            name: self.name.into_owned().into(),
+            operator: self.operator,
+            value: self.value.into_owned(),
+            flag: self.flag,
+            span: self.span,
+        }
+    }
+}
+
+impl<'a> TransformCss<Span> for SelectorAttr<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        f(&mut self.span)
+    }
+}
+
+impl<'a> RenderCss for SelectorAttr<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        if let Some(namespace) = &self.namespace {
+            write!(f, "{}|", namespace)?;
+        }
+
+        write!(f, "{}", self.name)?;
+        if let (Some(operator), Some(value)) = (self.operator, &self.value) {
+            write!(f, "{}{}", operator.as_str(), value)?;
+        }
+
+        match self.flag {
+            Some(AttrFlag::Insensitive) => write!(f, " i]"),
+            Some(AttrFlag::Sensitive) => write!(f, " s]"),
+            None => write!(f, "]"),
+        }
     }
 }
 
@@ -54,15 +211,17 @@ mod tests {
     use std::assert_matches::assert_matches;
 
     use super::*;
+    use crate::ast::{Qualifier, SelectorTerm};
 
     #[test]
     fn test_bool() {
         assert_matches!(
             SelectorAttr::parse::<()>("[disabled]"),
             Ok(("", SelectorAttr {
-                name: "disabled",
-                value: None
-            }))
+                name,
+                value: None,
+                ..
+            })) if name == "disabled"
         )
     }
 
@@ -71,21 +230,93 @@ mod tests {
         assert_matches!(
             SelectorAttr::parse::<()>("[data-value=\"red\"]"),
             Ok(("", SelectorAttr {
-                name: "data-value",
-                value: Some("\"red\"")
-            }))
+                name,
+                operator: Some(AttrOperator::Equals),
+                value: Some(value),
+                ..
+            })) if name == "data-value" && value == "\"red\""
         )
     }
 
-    #[ignore]
     #[test]
-    fn test_multiple() {
+    fn test_value_single_quotes() {
+        assert_matches!(
+            SelectorAttr::parse::<()>("[title='a]b']"),
+            Ok(("", SelectorAttr {
+                name,
+                value: Some(value),
+                ..
+            })) if name == "title" && value == "'a]b'"
+        )
+    }
+
+    #[test]
+    fn test_operators() {
+        for (input, expected) in [
+            ("[a~=b]", AttrOperator::Includes),
+            ("[a|=b]", AttrOperator::DashMatch),
+            ("[href^=\"http\"]", AttrOperator::Prefix),
+            ("[a$=b]", AttrOperator::Suffix),
+            ("[a*=b]", AttrOperator::Substring),
+        ] {
+            assert_matches!(
+                SelectorAttr::parse::<()>(input),
+                Ok(("", SelectorAttr { operator: Some(x), namespace: None, .. })) if x == expected
+            );
+
+            assert_eq!(
+                SelectorAttr::parse::<()>(input).unwrap().1.as_css_string(),
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_flag_and_namespace() {
         assert_matches!(
-            SelectorAttr::parse::<()>("[disabled,data-value=\"red\"]"),
+            SelectorAttr::parse::<()>("[ xlink|href = 'x' I ]"),
             Ok(("", SelectorAttr {
-                name: "data-value",
-                value: Some("\"red\"")
-            }))
+                namespace: Some(namespace),
+                name,
+                value: Some(value),
+                flag: Some(AttrFlag::Insensitive),
+                ..
+            })) if namespace == "xlink" && name == "href" && value == "'x'"
+        );
+
+        assert_matches!(
+            SelectorAttr::parse::<()>("[*|href]")
+                .map(|x| x.1.as_css_string())
+                .as_deref(),
+            Ok("[*|href]")
+        );
+
+        assert_matches!(
+            SelectorAttr::parse::<()>("[|href='x' s]")
+                .map(|x| x.1.as_css_string())
+                .as_deref(),
+            Ok("[|href='x' s]")
+        );
+    }
+
+    #[test]
+    fn test_multiple() {
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("input[disabled][data-value=\"red\"]"),
+            Ok(("", SelectorTerm {
+                qualifiers,
+                ..
+            })) if matches!(&qualifiers[..], [
+                Qualifier::Attribute(_),
+                Qualifier::Attribute(SelectorAttr { value: Some(value), .. }),
+            ] if value == "\"red\"")
+        );
+
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("input[disabled][data-value=\"red\"]")
+                .map(|x| x.1.as_css_string())
+                .as_deref(),
+            Ok("input[disabled][data-value=\"red\"]")
         )
     }
 }
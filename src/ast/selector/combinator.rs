@@ -11,20 +11,23 @@
 
 use winnow::{
     combinator::{alt, delimited},
-    error::ParserError,
     token::tag,
     IResult, Parser,
 };
 
-use crate::{ast::token::*, parser::*, render::*};
+use crate::{ast::token::*, owned::IntoOwned, parser::*, render::*};
 
 /// A selector combinator, used to combine a list of selectors.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Combinator {
     Null,
     Sibling,
     AdjSibling,
     Desc,
+
+    /// `||`, the column combinator.
+    Column,
 }
 
 impl RenderCss for Combinator {
@@ -34,14 +37,23 @@ impl RenderCss for Combinator {
             Combinator::Sibling => write!(f, "~"),
             Combinator::AdjSibling => write!(f, "+"),
             Combinator::Desc => write!(f, ">"),
+            Combinator::Column => write!(f, "||"),
         }
     }
 }
 
+impl IntoOwned for Combinator {
+    type Owned = Combinator;
+
+    fn into_owned(self) -> Self::Owned {
+        self
+    }
+}
+
 impl<'a> ParseCss<'a> for Combinator {
     fn parse<E>(input: &'a str) -> IResult<&'a str, Self, E>
     where
-        E: ParserError<&'a str>,
+        E: ParseCssError<'a>,
     {
         delimited(
             comment0,
@@ -49,6 +61,7 @@ impl<'a> ParseCss<'a> for Combinator {
                 tag("+").map(|_| Combinator::AdjSibling),
                 tag(">").map(|_| Combinator::Desc),
                 tag("~").map(|_| Combinator::Sibling),
+                tag("||").map(|_| Combinator::Column),
                 comment0.map(|_| Combinator::Null),
             )),
             comment0,
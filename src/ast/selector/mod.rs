@@ -14,25 +14,27 @@ mod combinator;
 mod selector_path;
 mod selector_term;
 
-use std::{hash::Hash, ops::Deref};
+use std::{borrow::Cow, hash::Hash, ops::Deref};
 
 use smallvec::SmallVec;
 use winnow::{
-    combinator::{delimited, preceded, repeat, separated},
-    error::ParserError,
+    combinator::{cut_err, delimited, eof, preceded, repeat, separated},
     token::tag,
     unpeek, IResult, Parser,
 };
 
 pub use self::{
-    attribute::SelectorAttr, combinator::Combinator, selector_path::SelectorPath,
-    selector_term::SelectorTerm,
+    attribute::{AttrFlag, AttrOperator, SelectorAttr},
+    combinator::Combinator,
+    selector_path::SelectorPath,
+    selector_term::{Nth, Pseudo, PseudoArg, PseudoMode, Qualifier, SelectorTerm},
 };
-use super::token::comment0;
-use crate::{parser::*, transform::TransformCss, utils::*};
+use super::{span::Span, token::comment0};
+use crate::{owned::IntoOwned, parser::*, transform::TransformCss, utils::*};
 
 /// A set of selector alternatives separated by `,`, for example `div, span`.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Selector<'a>(MinVec<SelectorPath<'a>, 1>);
 
 impl<'a> Default for Selector<'a> {
@@ -59,10 +61,18 @@ impl<'a> Deref for Selector<'a> {
     }
 }
 
+impl<'a> IntoOwned for Selector<'a> {
+    type Owned = Selector<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        Selector(self.0.into_owned())
+    }
+}
+
 impl<'a> ParseCss<'a> for Selector<'a> {
     fn parse<E>(input: &'a str) -> IResult<&'a str, Self, E>
     where
-        E: ParserError<&'a str>,
+        E: ParseCssError<'a>,
     {
         let (input, selector) = SelectorPath::parse(input)?;
         let (input, extra) = repeat(
@@ -78,6 +88,46 @@ impl<'a> ParseCss<'a> for Selector<'a> {
     }
 }
 
+impl<'a> From<SelectorPath<'a>> for Selector<'a> {
+    fn from(path: SelectorPath<'a>) -> Self {
+        Selector(MinVec::new([path], vec![]))
+    }
+}
+
+impl<'a> From<SelectorTerm<'a, Option<Cow<'a, str>>>> for Selector<'a> {
+    fn from(term: SelectorTerm<'a, Option<Cow<'a, str>>>) -> Self {
+        SelectorPath::Cons(term, vec![]).into()
+    }
+}
+
+/// Parse a selector list, e.g. `div > .open, span`.  Parse failures are
+/// reported as a [`crate::ParseError`].  The [`Span`]s of the parsed selector
+/// are empty, like those of other nodes which are not parsed from a
+/// stylesheet.
+///
+/// ```
+/// use procss::{ast::Selector, RenderCss};
+/// let selector = Selector::try_from("div > .open, span").unwrap();
+/// assert_eq!(selector.as_css_string(), "div>.open,span");
+/// assert!(Selector::try_from("div >").is_err());
+/// ```
+impl<'a> TryFrom<&'a str> for Selector<'a> {
+    type Error = anyhow::Error;
+
+    fn try_from(input: &'a str) -> anyhow::Result<Self> {
+        let (_, mut selector) = delimited(
+            comment0,
+            unpeek(Selector::parse),
+            (comment0, cut_err(eof).context("selector")),
+        )
+        .parse_peek(input)
+        .map_err(|err| unwrap_parse_error(input, err, None))?;
+
+        selector.transform_each(&mut |span: &mut Span| *span = Span::default());
+        Ok(selector)
+    }
+}
+
 impl<'a> TransformCss<SelectorPath<'a>> for Selector<'a> {
     fn transform_each<F: FnMut(&mut SelectorPath<'a>)>(&mut self, f: &mut F) {
         for list in self.0.iter_mut() {
@@ -86,13 +136,37 @@ impl<'a> TransformCss<SelectorPath<'a>> for Selector<'a> {
     }
 }
 
+impl<'a> TransformCss<Span> for Selector<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        for list in self.0.iter_mut() {
+            list.transform_each(f)
+        }
+    }
+}
+
 impl<'a> Selector<'a> {
-    /// Create a new `SelectorGroup` from an `Iterator`, which will fail if
-    /// there aren't enough elements.  This should asserted by the caller.
-    fn new(mut iter: impl Iterator<Item = SelectorPath<'a>>) -> Option<Self> {
+    /// Create a new [`Selector`] from its alternatives, or `None` if there are
+    /// none.
+    pub fn new(paths: impl IntoIterator<Item = SelectorPath<'a>>) -> Option<Self> {
+        let mut iter = paths.into_iter();
         Some(Selector(MinVec::new([iter.next()?], iter.collect())))
     }
 
+    /// Iterate mutably over the alternatives of this selector.
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &'_ mut SelectorPath<'a>> {
+        self.0.iter_mut()
+    }
+
+    /// The [`Span`] from the first to the last `SelectorPath` of this
+    /// `SelectorGroup`.
+    pub fn span(&self) -> Span {
+        let first = self.0.iter().next().unwrap().span();
+        match self.0.iter().last() {
+            Some(last) => first.join(last.span()),
+            None => first,
+        }
+    }
+
     /// `SelectorGroup` uses the underlying `join()` method of the
     /// `SelectorList`, combined via the product of the two
     /// `SelectorGroup`'s items.  For example:
@@ -112,8 +186,84 @@ impl<'a> Selector<'a> {
     ///     color: red;
     /// }
     /// ```
-    pub fn join(&self, other: &Selector<'a>) -> Selector<'a> {
-        let iter = self.0.iter().flat_map(|x| other.iter().map(|y| x.join(y)));
-        Self::new(iter).unwrap()
+    ///
+    /// A nested selector which contains `&` anywhere else, such as
+    /// `.theme-dark &` or `:not(&)`, is not prefixed with the parent selector;
+    /// instead, each `&` is replaced by the parent selector, as in the
+    /// [CSS Nesting](https://www.w3.org/TR/css-nesting-1/) spec.
+    ///
+    /// Returns `None` if `other` concatenates a suffix to a `&` which this
+    /// selector can't take, e.g. `&__elem` nested in `.block:hover`, or
+    /// `.theme-dark &__elem` nested in `.a, .b`.
+    pub fn join(&self, other: &Selector<'a>) -> Option<Selector<'a>> {
+        let other = other
+            .iter()
+            .map(|y| Some((y.has_nesting(), y.resolve_nested(self)?)))
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut paths = vec![];
+        for (idx, x) in self.0.iter().enumerate() {
+            for (nesting, y) in other.iter() {
+                match y {
+                    SelectorPath::Cons(..) if *nesting => {
+                        if idx == 0 {
+                            paths.push(y.clone());
+                        }
+                    }
+                    _ => paths.push(x.join(y)?),
+                }
+            }
+        }
+
+        Self::new(paths)
+    }
+
+    /// Replace the `&` references in the selector arguments of pseudo-classes
+    /// (such as `:is()`) in this selector with `parent`.  For example,
+    /// `:not(&.open)` nested in `div, span` becomes
+    /// `:not(div.open, span.open)`.
+    fn resolve_nested(&self, parent: &Selector<'a>) -> Option<Selector<'a>> {
+        let mut paths = vec![];
+        for path in self.iter() {
+            let path = path.resolve_nested(parent)?;
+            match path {
+                SelectorPath::PartialCons(..) => {
+                    for x in parent.iter() {
+                        paths.push(x.join(&path)?);
+                    }
+                }
+                SelectorPath::Cons(..) => paths.push(path),
+            }
+        }
+
+        Self::new(paths)
+    }
+
+    /// Does any path of this selector concatenate a suffix to a `&`, e.g.
+    /// `&__elem`?
+    pub(super) fn has_suffix(&self) -> bool {
+        self.iter().any(|x| x.has_suffix())
+    }
+
+    /// The term a `&` which is not the first term of a path is replaced with;
+    /// this selector itself if it is a single compound selector, otherwise
+    /// `:is()` of this selector.  A single compound selector which starts with
+    /// `&` itself, e.g. `&.open`, is replaced with a `&` term with its
+    /// qualifiers, which is resolved when this selector's parent is joined.
+    fn nesting_term(&self) -> SelectorTerm<'a, Option<Cow<'a, str>>> {
+        match self.iter().collect::<Vec<_>>()[..] {
+            [SelectorPath::Cons(term, tail)] if tail.is_empty() => term.clone(),
+            [SelectorPath::PartialCons(term, tail)] if tail.is_empty() => SelectorTerm {
+                nesting: true,
+                tag: None,
+                suffix: term.suffix.clone(),
+                qualifiers: term.qualifiers.clone(),
+                ..SelectorTerm::default()
+            },
+            _ => SelectorTerm {
+                qualifiers: vec![Qualifier::Pseudo(Box::new(Pseudo::is(self.clone())))],
+                ..SelectorTerm::default()
+            },
+        }
     }
 }
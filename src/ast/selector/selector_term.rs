@@ -9,52 +9,248 @@
 // │                                                                           │
 // └───────────────────────────────────────────────────────────────────────────┘
 
+use std::borrow::Cow;
+
 use winnow::{
-    combinator::{alt, delimited, opt, preceded, repeat},
+    ascii::{digit1, multispace1},
+    combinator::{alt, cut_err, delimited, not, opt, preceded, repeat, separated, terminated},
     error::ParserError,
-    token::tag,
-    unpeek, IResult, Parser,
+    token::{one_of, tag, tag_no_case, take_till1},
+    unpeek, IResult, PResult, Parser,
 };
 
-use super::attribute::SelectorAttr;
-use crate::{ast::token::*, parser::*, render::*};
+use super::{
+    attribute::SelectorAttr, combinator::Combinator, selector_path::SelectorPath, Selector,
+};
+use crate::{
+    ast::{token::*, Span},
+    owned::IntoOwned,
+    parser::*,
+    render::*,
+    transform::TransformCss,
+};
 
 /// pseudo-selectors can be "pseudo-class" or "pseudo-element", and we are only
 /// concerned about the distinction between them in regards to their syntax.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PseudoMode {
     PseudoClass,
     PseudoElement,
 }
 
-/// A pseudo-selector component of a `Selector`, including optional argument
-/// selector (parenthesis delimited).
+/// A pseudo-selector component of a `Selector`, including optional
+/// (parenthesis delimited) arguments.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pseudo<'a> {
-    property: &'a str,
-    value: Option<SelectorTerm<'a, Option<&'a str>>>,
-    mode: PseudoMode,
+    pub property: Cow<'a, str>,
+    pub value: Option<PseudoArg<'a>>,
+    pub mode: PseudoMode,
+    pub span: Span,
+}
+
+/// The arguments of a functional pseudo-class, parsed by the kind of the
+/// pseudo-class.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PseudoArg<'a> {
+    /// A selector list, e.g. `:is(.a, div > p)`.  Also used for `:where()`
+    /// and `:not()`.
+    Selector(Selector<'a>),
+
+    /// A relative selector list, e.g. `:has(> img, + p)`.  Selectors without
+    /// a leading combinator have [`Combinator::Null`].
+    Relative(Vec<(Combinator, SelectorPath<'a>)>),
+
+    /// An `An+B` argument, e.g. `:nth-child(2n+1 of .row)`.
+    Nth(Nth<'a>),
+
+    /// The arguments of any other function, as written.
+    Raw(Cow<'a, str>),
+}
+
+/// The `An+B` argument of the `:nth-*` family of pseudo-classes, with its
+/// optional `of` selector list.  `odd` and `even` are parsed as `2n+1` and
+/// `2n`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Nth<'a> {
+    pub a: i32,
+    pub b: i32,
+    pub of: Option<Selector<'a>>,
 }
 
 impl<'a> ParseCss<'a> for Pseudo<'a> {
-    fn parse<E: ParserError<&'a str>>(input: &'a str) -> IResult<&'a str, Self, E> {
-        let (input, mode) = (tag(":"), opt(tag(":"))).parse_peek(input)?;
-        let (input, property) = parse_symbol.parse_peek(input)?;
-        let (input, value) =
-            opt(delimited(tag("("), unpeek(SelectorTerm::parse), tag(")"))).parse_peek(input)?;
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        let (rest, mode) = (tag(":"), opt(tag(":"))).parse_peek(input)?;
+        let (rest, property) = parse_ident.parse_peek(rest)?;
+        let (rest, value) = opt(delimited(
+            tag("("),
+            alt((
+                terminated(pseudo_arg(property), comment0),
+                parse_raw.map(|x: &'a str| PseudoArg::Raw(x.into())),
+            )),
+            tag(")"),
+        ))
+        .parse_peek(rest)?;
+
         let mode = mode
             .1
             .map(|_| PseudoMode::PseudoElement)
             .unwrap_or(PseudoMode::PseudoClass);
 
-        Ok((input, Pseudo {
-            property,
+        Ok((rest, Pseudo {
+            property: property.into(),
             value,
             mode,
+            span: Span::parsed(input, rest),
         }))
     }
 }
 
+/// Parse the arguments of the pseudo-class `property` by its kind, failing
+/// for unknown pseudo-classes.
+fn pseudo_arg<'a, E: ParseCssError<'a>>(
+    property: &'a str,
+) -> impl Parser<&'a str, PseudoArg<'a>, E> {
+    move |input: &mut &'a str| {
+        comment0.parse_next(input)?;
+        match property.to_ascii_lowercase().as_str() {
+            "is" | "where" | "not" | "matches" => unpeek(Selector::parse)
+                .map(PseudoArg::Selector)
+                .parse_next(input),
+            "has" => separated(
+                1..,
+                unpeek(parse_relative),
+                delimited(comment0, tag(","), comment0),
+            )
+            .map(PseudoArg::Relative)
+            .parse_next(input),
+            "nth-child" | "nth-last-child" | "nth-of-type" | "nth-last-of-type" => {
+                unpeek(Nth::parse).map(PseudoArg::Nth).parse_next(input)
+            }
+            _ => Err(winnow::error::ErrMode::Backtrack(E::from_error_kind(
+                input,
+                winnow::error::ErrorKind::Verify,
+            ))),
+        }
+    }
+}
+
+/// Parse a selector with an optional leading combinator.
+fn parse_relative<'a, E: ParseCssError<'a>>(
+    input: &'a str,
+) -> IResult<&'a str, (Combinator, SelectorPath<'a>), E> {
+    let combinator = alt((
+        tag(">").value(Combinator::Desc),
+        tag("+").value(Combinator::AdjSibling),
+        tag("~").value(Combinator::Sibling),
+    ));
+
+    (
+        opt(terminated(combinator, comment0)).map(|x| x.unwrap_or(Combinator::Null)),
+        unpeek(SelectorPath::parse),
+    )
+        .parse_peek(input)
+}
+
+/// Parse balanced text up to the closing `)` of a function.
+fn parse_raw<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    repeat::<_, _, (), _, _>(
+        0..,
+        alt((
+            take_till1(['(', ')', '"', '\'']),
+            parse_string_literal(),
+            ("(", parse_raw, ")").recognize(),
+        )),
+    )
+    .recognize()
+    .parse_next(input)
+}
+
+impl<'a> ParseCss<'a> for Nth<'a> {
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        let sign = || opt(one_of(['+', '-'])).map(|x| if x == Some('-') { -1 } else { 1 });
+        let number = || digit1.verify_map(|x: &str| x.parse::<i32>().ok());
+        let b = preceded(comment0, (one_of(['+', '-']), preceded(comment0, number())))
+            .map(|(sign, b)| if sign == '-' { -b } else { b });
+
+        let (rest, (a, b)) = alt((
+            tag_no_case("odd").value((2, 1)),
+            tag_no_case("even").value((2, 0)),
+            (sign(), opt(number()), one_of(['n', 'N']), opt(b))
+                .map(|(sign, a, _, b)| (sign * a.unwrap_or(1), b.unwrap_or(0))),
+            (sign(), number()).map(|(sign, b)| (0, sign * b)),
+        ))
+        .parse_peek(input)?;
+
+        let (rest, of) = opt(preceded(
+            (multispace1, tag_no_case("of"), multispace1),
+            unpeek(Selector::parse),
+        ))
+        .parse_peek(rest)?;
+
+        Ok((rest, Nth { a, b, of }))
+    }
+}
+
+impl<'a> IntoOwned for Pseudo<'a> {
+    type Owned = Pseudo<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        Pseudo {
+            property: self.property.into_owned().into(),
+            value: self.value.into_owned(),
+            mode: self.mode,
+            span: self.span,
+        }
+    }
+}
+
+impl<'a> IntoOwned for PseudoArg<'a> {
+    type Owned = PseudoArg<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            PseudoArg::Selector(x) => PseudoArg::Selector(x.into_owned()),
+            PseudoArg::Relative(x) => PseudoArg::Relative(x.into_owned()),
+            PseudoArg::Nth(x) => PseudoArg::Nth(x.into_owned()),
+            PseudoArg::Raw(x) => PseudoArg::Raw(x.into_owned().into()),
+        }
+    }
+}
+
+impl<'a> IntoOwned for Nth<'a> {
+    type Owned = Nth<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        Nth {
+            a: self.a,
+            b: self.b,
+            of: self.of.into_owned(),
+        }
+    }
+}
+
+impl<'a> TransformCss<Span> for Pseudo<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        f(&mut self.span);
+        match &mut self.value {
+            Some(PseudoArg::Selector(selector))
+            | Some(PseudoArg::Nth(Nth {
+                of: Some(selector), ..
+            })) => selector.transform_each(f),
+            Some(PseudoArg::Relative(paths)) => {
+                for (_, path) in paths.iter_mut() {
+                    path.transform_each(f);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
 impl<'a> RenderCss for Pseudo<'a> {
     fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.mode {
@@ -72,163 +268,502 @@ impl<'a> RenderCss for Pseudo<'a> {
     }
 }
 
-enum SelType<'a> {
-    Class(&'a str),
-    Id(&'a str),
-    Pseudo(Pseudo<'a>),
-    Attr(SelectorAttr<'a>),
+impl<'a> RenderCss for PseudoArg<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PseudoArg::Selector(selector) => selector.render(f),
+            PseudoArg::Relative(paths) => {
+                for (idx, (combinator, path)) in paths.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    if *combinator != Combinator::Null {
+                        combinator.render(f)?;
+                    }
+
+                    path.render(f)?;
+                }
+
+                Ok(())
+            }
+            PseudoArg::Nth(nth) => nth.render(f),
+            PseudoArg::Raw(raw) => write!(f, "{}", raw.trim()),
+        }
+    }
+}
+
+impl<'a> RenderCss for Nth<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.a, self.b) {
+            (0, b) => write!(f, "{}", b)?,
+            (a, b) => {
+                match a {
+                    1 => write!(f, "n")?,
+                    -1 => write!(f, "-n")?,
+                    a => write!(f, "{}n", a)?,
+                }
+
+                if b != 0 {
+                    write!(f, "{:+}", b)?;
+                }
+            }
+        }
+
+        if let Some(of) = &self.of {
+            write!(f, " of ")?;
+            of.render(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Pseudo<'a> {
+    /// A `:is()` pseudo-class of `selector`.
+    pub(super) fn is(selector: Selector<'a>) -> Self {
+        Pseudo {
+            property: "is".into(),
+            value: Some(PseudoArg::Selector(selector)),
+            mode: PseudoMode::PseudoClass,
+            span: Span::default(),
+        }
+    }
+
+    fn has_nesting(&self) -> bool {
+        match &self.value {
+            Some(PseudoArg::Selector(selector))
+            | Some(PseudoArg::Nth(Nth {
+                of: Some(selector), ..
+            })) => selector.iter().any(|x| x.has_nesting()),
+            Some(PseudoArg::Relative(paths)) => paths.iter().any(|(_, x)| x.has_nesting()),
+            _ => false,
+        }
+    }
+
+    fn has_suffix(&self) -> bool {
+        match &self.value {
+            Some(PseudoArg::Selector(selector))
+            | Some(PseudoArg::Nth(Nth {
+                of: Some(selector), ..
+            })) => selector.has_suffix(),
+            Some(PseudoArg::Relative(paths)) => paths.iter().any(|(_, x)| x.has_suffix()),
+            _ => false,
+        }
+    }
+
+    /// Replace the `&` references in this pseudo-class's selector arguments
+    /// with `parent`, or `None` if a `&` suffix can't be joined to `parent`.
+    fn resolve_nested(&self, parent: &Selector<'a>) -> Option<Self> {
+        let value = match &self.value {
+            Some(PseudoArg::Selector(selector)) => {
+                Some(PseudoArg::Selector(selector.resolve_nested(parent)?))
+            }
+            Some(PseudoArg::Relative(paths)) => {
+                let mut resolved = vec![];
+                for (combinator, path) in paths.iter() {
+                    let selector = Selector::from(path.clone()).resolve_nested(parent)?;
+                    resolved.extend(selector.iter().map(|x| (*combinator, x.clone())));
+                }
+
+                Some(PseudoArg::Relative(resolved))
+            }
+            Some(PseudoArg::Nth(Nth { a, b, of: Some(of) })) => Some(PseudoArg::Nth(Nth {
+                a: *a,
+                b: *b,
+                of: Some(of.resolve_nested(parent)?),
+            })),
+            x => x.clone(),
+        };
+
+        Some(Pseudo {
+            value,
+            ..self.clone()
+        })
+    }
+}
+
+/// A simple selector which qualifies the type selector of a [`SelectorTerm`],
+/// e.g. `#a`, `.b`, `[c]` or `:hover`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Qualifier<'a> {
+    Id(Cow<'a, str>),
+    Class(Cow<'a, str>),
+    Attribute(SelectorAttr<'a>),
+    Pseudo(Box<Pseudo<'a>>),
+}
+
+impl<'a> Qualifier<'a> {
+    fn is_pseudo_element(&self) -> bool {
+        matches!(self, Qualifier::Pseudo(x) if x.mode == PseudoMode::PseudoElement)
+    }
+}
+
+impl<'a> ParseCss<'a> for Qualifier<'a> {
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        alt((
+            preceded(tag("."), parse_ident).map(|x| Qualifier::Class(x.into())),
+            preceded(tag("#"), parse_name).map(|x| Qualifier::Id(x.into())),
+            unpeek(Pseudo::parse).map(|x| Qualifier::Pseudo(Box::new(x))),
+            unpeek(SelectorAttr::parse).map(Qualifier::Attribute),
+        ))
+        .parse_peek(input)
+    }
+}
+
+impl<'a> IntoOwned for Qualifier<'a> {
+    type Owned = Qualifier<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            Qualifier::Id(x) => Qualifier::Id(x.into_owned().into()),
+            Qualifier::Class(x) => Qualifier::Class(x.into_owned().into()),
+            Qualifier::Attribute(x) => Qualifier::Attribute(x.into_owned()),
+            Qualifier::Pseudo(x) => Qualifier::Pseudo(Box::new((*x).into_owned())),
+        }
+    }
+}
+
+impl<'a> TransformCss<Span> for Qualifier<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        match self {
+            Qualifier::Id(_) | Qualifier::Class(_) => (),
+            Qualifier::Attribute(x) => x.transform_each(f),
+            Qualifier::Pseudo(x) => x.transform_each(f),
+        }
+    }
+}
+
+impl<'a> RenderCss for Qualifier<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Qualifier::Id(x) => write!(f, "#{}", x),
+            Qualifier::Class(x) => write!(f, ".{}", x),
+            Qualifier::Attribute(x) => x.render(f),
+            Qualifier::Pseudo(x) => x.render(f),
+        }
+    }
 }
 
 /// A single compound CSS selector, parameterized over it's `tag` field such
 /// that the uniqu wildcard and self selectors can re-use the same struct and
 /// some tag-irrelevent functions can be shared between impls.
 #[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SelectorTerm<'a, T> {
-    pub id: Option<&'a str>,
-    pub class: Vec<&'a str>,
+    /// The namespace prefix of `tag`, e.g. `svg` in `svg|rect`, which is `*`
+    /// for any namespace and `""` for no namespace (`|rect`).  Prefixes are
+    /// declared with `@namespace`, see [`crate::ast::Css::namespace`].
+    pub namespace: Option<Cow<'a, str>>,
+
+    /// Is this term the nesting selector `&`, when it is not the first term
+    /// of a [`SelectorPath`] (see [`SelectorPath::PartialCons`]), e.g. in
+    /// `.theme-dark &`.
+    pub nesting: bool,
     pub tag: T,
-    pub attribute: Vec<SelectorAttr<'a>>,
-    pub pseudo: Vec<Pseudo<'a>>,
+
+    /// A suffix concatenated to the parent selector by `&`, e.g. `__elem` in
+    /// `&__elem`.  Once joined, this is only set when the parent selector
+    /// ended in a type selector or another `&`, see [`SelectorTerm::join`].
+    pub suffix: Option<Cow<'a, str>>,
+
+    /// The ids, classes, attribute selectors and pseudo-selectors of this
+    /// term, in source order.
+    pub qualifiers: Vec<Qualifier<'a>>,
+    pub span: Span,
+}
+
+impl<'a, T> SelectorTerm<'a, T> {
+    /// The ids of this term, e.g. `a` and `c` in `div#a.b#c`.
+    pub fn ids(&self) -> impl Iterator<Item = &str> + '_ {
+        self.qualifiers.iter().filter_map(|x| match x {
+            Qualifier::Id(x) => Some(x.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// The classes of this term, e.g. `b` in `div#a.b#c`.
+    pub fn classes(&self) -> impl Iterator<Item = &str> + '_ {
+        self.qualifiers.iter().filter_map(|x| match x {
+            Qualifier::Class(x) => Some(x.as_ref()),
+            _ => None,
+        })
+    }
 }
 
 impl<'a, T: Clone> SelectorTerm<'a, T> {
     /// Create a new `Selector`.
-    fn new(tag: T, qualifiers: &[SelType<'a>]) -> SelectorTerm<'a, T> {
-        let mut class = vec![];
-        let mut id: Option<&str> = None;
-        let mut attribute = vec![];
-        let mut pseudo = vec![];
-        for x in qualifiers {
-            match x {
-                SelType::Class(x) => class.push(*x),
-                SelType::Id(x) => id = Some(x),
-                SelType::Pseudo(x) => pseudo.push(x.clone()),
-                SelType::Attr(x) => attribute.push(x.clone()),
+    fn new(tag: T, qualifiers: Vec<Qualifier<'a>>, span: Span) -> SelectorTerm<'a, T> {
+        SelectorTerm {
+            namespace: None,
+            nesting: false,
+            tag,
+            suffix: None,
+            qualifiers,
+            span,
+        }
+    }
+
+    /// Join to another "self" selector, appending `other`'s qualifiers after
+    /// this selector's.  Pseudo-elements are moved after all other
+    /// qualifiers, so `&::before` joined with `:hover` is `:hover::before`.
+    ///
+    /// `other`'s suffix is concatenated to this selector's last simple
+    /// selector, so `.block` joined with `&__elem` is `.block__elem`, unless
+    /// `accepts_suffix` is false.
+    fn join_term(&self, other: &SelectorTerm<'a, ()>, accepts_suffix: bool) -> Option<Self> {
+        let mut qualifiers = self.qualifiers.clone();
+        let mut suffix = self.suffix.clone();
+        if let Some(x) = &other.suffix {
+            if !accepts_suffix {
+                return None;
+            }
+
+            match qualifiers.last_mut() {
+                Some(Qualifier::Class(last) | Qualifier::Id(last)) => {
+                    *last = format!("{}{}", last, x).into()
+                }
+                _ => {
+                    suffix = Some(match suffix {
+                        Some(y) => format!("{}{}", y, x).into(),
+                        None => x.clone(),
+                    })
+                }
             }
         }
 
+        qualifiers.extend(other.qualifiers.iter().cloned());
+        qualifiers.sort_by_key(|x| x.is_pseudo_element());
+        Some(SelectorTerm {
+            namespace: self.namespace.clone(),
+            nesting: self.nesting,
+            tag: self.tag.clone(),
+            suffix,
+            qualifiers,
+            span: self.span,
+        })
+    }
+
+    /// Does the last qualifier of this term take a `&` suffix, or `None` if
+    /// this term has no qualifiers and the suffix goes to its tag?
+    fn qualifier_accepts_suffix(&self) -> Option<bool> {
+        self.qualifiers
+            .last()
+            .map(|x| matches!(x, Qualifier::Id(_) | Qualifier::Class(_)))
+    }
+}
+
+impl<'a> SelectorTerm<'a, Option<Cow<'a, str>>> {
+    /// Join to another "self" selector, appending `other`'s qualifiers after
+    /// this selector's.  Pseudo-elements are moved after all other
+    /// qualifiers, so `&::before` joined with `:hover` is `:hover::before`.
+    ///
+    /// `other`'s suffix is concatenated to this selector's last simple
+    /// selector, so `.block` joined with `&__elem` is `.block__elem`.  Returns
+    /// `None` if the last simple selector can't take a suffix, see
+    /// [`Self::accepts_suffix`].
+    pub fn join(&self, other: &SelectorTerm<'a, ()>) -> Option<Self> {
+        self.join_term(other, self.accepts_suffix())
+    }
+
+    /// Can a `&` suffix be concatenated to this term?  Only terms which end in
+    /// a class, id, type selector or `&` can take a suffix, so `.block` and
+    /// `.a:hover.block` can but `.block:hover`, `[data-block]` and `*`
+    /// can't.
+    pub fn accepts_suffix(&self) -> bool {
+        self.qualifier_accepts_suffix()
+            .unwrap_or(self.nesting || self.tag.as_ref().is_some_and(|x| x != "*"))
+    }
+}
+
+impl<'a> SelectorTerm<'a, ()> {
+    /// Join to another "self" selector, as for a term with a tag.  A suffix
+    /// joined to a `&` with no qualifiers is kept until the `&` is itself
+    /// joined to its parent selector.
+    pub fn join(&self, other: &SelectorTerm<'a, ()>) -> Option<Self> {
+        self.join_term(other, self.accepts_suffix())
+    }
+
+    /// Can a `&` suffix be concatenated to this term?  A `&` with no
+    /// qualifiers can, deferring to its parent selector.
+    pub fn accepts_suffix(&self) -> bool {
+        self.qualifier_accepts_suffix().unwrap_or(true)
+    }
+}
+
+impl<'a, T: Clone> SelectorTerm<'a, T> {
+    /// Does this term contain a `&` reference, either as the term itself or in
+    /// the arguments of its pseudo-classes?
+    pub(super) fn has_nesting(&self) -> bool {
+        self.nesting
+            || self.qualifiers.iter().any(|x| match x {
+                Qualifier::Pseudo(x) => x.has_nesting(),
+                _ => false,
+            })
+    }
+
+    /// Does this term concatenate a suffix to a `&`, either as the term itself
+    /// or in the arguments of its pseudo-classes?
+    pub(super) fn has_suffix(&self) -> bool {
+        self.suffix.is_some()
+            || self.qualifiers.iter().any(|x| match x {
+                Qualifier::Pseudo(x) => x.has_suffix(),
+                _ => false,
+            })
+    }
+
+    /// The qualifiers of this term, without its tag.
+    pub(super) fn without_tag(&self) -> SelectorTerm<'a, ()> {
         SelectorTerm {
-            id,
-            class,
-            tag,
-            attribute,
-            pseudo,
+            namespace: None,
+            nesting: false,
+            tag: (),
+            suffix: self.suffix.clone(),
+            qualifiers: self.qualifiers.clone(),
+            span: self.span,
         }
     }
 
-    /// Join to another "self" selector.
-    /// TODO Joining two selectors with populated `id` fields will discard the
-    /// parent's `id`.
-    pub fn join(&self, other: &SelectorTerm<'a, ()>) -> Self {
-        let mut class = self.class.clone();
-        let mut attribute = self.attribute.clone();
-        let mut pseudo = self.pseudo.clone();
-        let id = other.id.or(self.id);
-        class.append(&mut other.class.clone());
-        attribute.append(&mut other.attribute.clone());
-        pseudo.append(&mut other.pseudo.clone());
+    /// Replace the `&` references in the arguments of this term's
+    /// pseudo-classes with `parent`, or `None` if a `&` suffix can't be
+    /// joined to `parent`.
+    pub(super) fn resolve_nested(&self, parent: &Selector<'a>) -> Option<Self> {
+        Some(SelectorTerm {
+            qualifiers: self
+                .qualifiers
+                .iter()
+                .map(|x| match x {
+                    Qualifier::Pseudo(x) => {
+                        Some(Qualifier::Pseudo(Box::new(x.resolve_nested(parent)?)))
+                    }
+                    x => Some(x.clone()),
+                })
+                .collect::<Option<_>>()?,
+            ..self.clone()
+        })
+    }
+}
+
+impl<'a, T: IntoOwned> IntoOwned for SelectorTerm<'a, T> {
+    type Owned = SelectorTerm<'static, T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
         SelectorTerm {
-            id,
-            class,
-            tag: self.tag.clone(),
-            attribute,
-            pseudo,
+            namespace: self.namespace.into_owned(),
+            nesting: self.nesting,
+            tag: self.tag.into_owned(),
+            suffix: self.suffix.into_owned(),
+            qualifiers: self.qualifiers.into_owned(),
+            span: self.span,
+        }
+    }
+}
+
+impl<'a, T> TransformCss<Span> for SelectorTerm<'a, T> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        f(&mut self.span);
+        for qualifier in self.qualifiers.iter_mut() {
+            qualifier.transform_each(f);
         }
     }
 }
 
 impl<'a, T: RenderCss> RenderCss for SelectorTerm<'a, T> {
     fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.tag.render(f)?;
-        if let Some(tag) = &self.id {
-            write!(f, "#{}", tag)?;
+        if self.nesting {
+            write!(f, "&")?;
         }
 
-        for class in &self.class {
-            write!(f, ".{}", class)?;
+        if let Some(namespace) = &self.namespace {
+            write!(f, "{}|", namespace)?;
         }
 
-        if !self.attribute.is_empty() {
-            write!(f, "[")?;
-            let mut first = true;
-            for SelectorAttr { name, value } in &self.attribute {
-                if !first {
-                    write!(f, ",")?;
-                }
-
-                write!(f, "{}", name)?;
-                if let Some(val) = value {
-                    write!(f, "={}", val)?;
-                }
-
-                first = false;
-            }
-
-            write!(f, "]")?;
+        self.tag.render(f)?;
+        if let Some(suffix) = &self.suffix {
+            write!(f, "{}", suffix)?;
         }
 
-        for class in &self.pseudo {
-            class.render(f)?;
+        for qualifier in &self.qualifiers {
+            qualifier.render(f)?;
         }
 
         Ok(())
     }
 }
 
-// TODO multiple ids dont work correctly, we discard all but last
-
-impl<'a> ParseCss<'a> for SelectorTerm<'a, Option<&'a str>> {
+impl<'a> ParseCss<'a> for SelectorTerm<'a, Option<Cow<'a, str>>> {
     fn parse<E>(input: &'a str) -> IResult<&'a str, Self, E>
     where
-        E: ParserError<&'a str>,
+        E: ParseCssError<'a>,
     {
-        let (rest, (tag, qualifiers)) = (
-            opt(parse_symbol),
-            repeat::<_, _, Vec<_>, _, _>(
-                0..,
-                alt((
-                    preceded(tag("."), parse_symbol.map(SelType::Class)),
-                    preceded(tag("#"), parse_symbol.map(SelType::Id)),
-                    unpeek(Pseudo::parse).map(SelType::Pseudo),
-                    unpeek(SelectorAttr::parse).map(SelType::Attr),
-                )),
-            ),
+        let (rest, ((suffix, namespace, tag), qualifiers)) = (
+            alt((
+                preceded(tag("&"), opt(parse_name)).map(|x| (Some(x), None, None)),
+                (
+                    opt(terminated(
+                        alt((parse_ident, tag("*"), tag(""))),
+                        (tag("|"), not(tag("|"))),
+                    )),
+                    opt(alt((parse_ident, tag("*")))),
+                )
+                    .map(|(namespace, tag)| (None, namespace, tag)),
+            )),
+            repeat::<_, _, Vec<_>, _, _>(0.., unpeek(Qualifier::parse)),
         )
             .parse_peek(input)?;
 
-        if tag.is_none() && qualifiers.is_empty() {
+        let (rest, _) = inner_nesting.parse_peek(rest)?;
+        let nesting = suffix.is_some();
+        if (!nesting && tag.is_none() && qualifiers.is_empty())
+            || (namespace.is_some() && tag.is_none())
+        {
             return winnow::IResult::Err(winnow::error::ErrMode::Backtrack(
                 ParserError::from_error_kind(&rest, winnow::error::ErrorKind::Verify),
             ));
         }
 
-        Ok((rest, SelectorTerm::new(tag, &qualifiers)))
+        let span = Span::parsed(input, rest);
+        let term = SelectorTerm::new(tag.map(Cow::Borrowed), qualifiers, span);
+        Ok((rest, SelectorTerm {
+            namespace: namespace.map(Cow::Borrowed),
+            nesting,
+            suffix: suffix.flatten().map(Cow::Borrowed),
+            ..term
+        }))
     }
 }
 
 impl<'a> ParseCss<'a> for SelectorTerm<'a, ()> {
     fn parse<E>(input: &'a str) -> IResult<&'a str, Self, E>
     where
-        E: ParserError<&'a str>,
+        E: ParseCssError<'a>,
     {
-        let (rest, (_, qualifiers)) = (
+        let (rest, (_, suffix, qualifiers)) = (
             tag("&"),
-            repeat::<_, _, Vec<_>, _, _>(
-                0..,
-                alt((
-                    preceded(tag("."), parse_symbol.map(SelType::Class)),
-                    preceded(tag("#"), parse_symbol.map(SelType::Id)),
-                    unpeek(Pseudo::parse).map(SelType::Pseudo),
-                    unpeek(SelectorAttr::parse).map(SelType::Attr),
-                )),
-            ),
+            opt(parse_name),
+            repeat::<_, _, Vec<_>, _, _>(0.., unpeek(Qualifier::parse)),
         )
             .parse_peek(input)?;
 
-        Ok((rest, SelectorTerm::new((), &qualifiers)))
+        let (rest, _) = inner_nesting.parse_peek(rest)?;
+        let span = Span::parsed(input, rest);
+        let term = SelectorTerm::new((), qualifiers, span);
+        Ok((rest, SelectorTerm {
+            suffix: suffix.map(Cow::Borrowed),
+            ..term
+        }))
     }
 }
 
+/// A `&` may only start a compound selector, e.g. `&.x` rather than `.x&`,
+/// which would otherwise be parsed as the descendant selector `.x &`.
+fn inner_nesting<'a, E: ParseCssError<'a>>(input: &mut &'a str) -> PResult<(), E> {
+    cut_err(not(tag("&")))
+        .context("combinator before `&`")
+        .parse_next(input)
+}
+
 #[cfg(test)]
 mod tests {
     use std::assert_matches::assert_matches;
@@ -238,111 +773,114 @@ mod tests {
     #[test]
     fn test_tag() {
         assert_matches!(
-            SelectorTerm::parse::<()>("--column-selector--background"),
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("--column-selector--background"),
             Ok(("", SelectorTerm {
-                tag: Some("--column-selector--background"),
+                tag: Some(tag),
                 ..
-            }))
+            })) if tag == "--column-selector--background"
         )
     }
 
     #[test]
     fn test_class() {
         assert_matches!(
-            SelectorTerm::<Option<&str>>::parse::<()>(".column-selector--background"),
-            Ok(("", SelectorTerm {
-                class,
-                ..
-            })) if class == vec!["column-selector--background"]
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>(".column-selector--background"),
+            Ok(("", term)) if term.classes().eq(["column-selector--background"])
         )
     }
 
     #[test]
     fn test_classes() {
         assert_matches!(
-            SelectorTerm::<Option<&str>>::parse::<()>(".column-selector.column-selector--background"),
-            Ok(("", SelectorTerm {
-                class,
-                ..
-            })) if class == vec!["column-selector", "column-selector--background"]
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>(".column-selector.column-selector--background"),
+            Ok(("", term)) if term.classes().eq(["column-selector", "column-selector--background"])
         )
     }
 
     #[test]
     fn test_attribute() {
         assert_matches!(
-            SelectorTerm::<Option<&str>>::parse::<()>("[name=test]"),
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("[name=test]"),
             Ok(("", SelectorTerm {
-                attribute,
+                qualifiers,
                 ..
-            })) if attribute == vec![SelectorAttr{ name: "name", value: Some("test") }]
+            })) if matches!(&qualifiers[..], [Qualifier::Attribute(x)] if x.name == "name" && x.value.as_deref() == Some("test"))
         )
     }
 
     #[test]
     fn test_id() {
         assert_matches!(
-            SelectorTerm::<Option<&str>>::parse::<()>("#column-selector--background"),
-            Ok(("", SelectorTerm {
-                id: Some("column-selector--background"),
-                ..
-            }))
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("#column-selector--background"),
+            Ok(("", term)) if term.ids().eq(["column-selector--background"])
         )
     }
 
+    #[test]
+    fn test_ids() {
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("div#a.b#c")
+                .map(|x| x.1.as_css_string())
+                .as_deref(),
+            Ok("div#a.b#c")
+        )
+    }
+
+    #[test]
+    fn test_suffix() {
+        let (_, parent) = SelectorTerm::<Option<Cow<str>>>::parse::<()>("div#a.b").unwrap();
+        let (_, child) = SelectorTerm::<()>::parse::<()>("&__c.d").unwrap();
+        assert_eq!(parent.join(&child).unwrap().as_css_string(), "div#a.b__c.d");
+    }
+
     #[test]
     fn test_id_class_tag() {
         assert_matches!(
-            SelectorTerm::<Option<&str>>::parse::<()>("div#column-selector.column-selector.column-selector--background"),
-            Ok(("", SelectorTerm {
-                id: Some("column-selector"),
-                class,
-                tag: Some("div"),
-                ..
-            }))if class == vec!["column-selector", "column-selector--background"]
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("div#column-selector.column-selector.column-selector--background"),
+            Ok(("", term)) if term.tag.as_deref() == Some("div") && term.ids().eq(["column-selector"]) && term.classes().eq(["column-selector", "column-selector--background"])
         )
     }
 
     #[test]
     fn test_pesudo() {
         assert_matches!(
-            SelectorTerm::parse::<()>("div:hover"),
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("div:hover"),
             Ok(("", SelectorTerm {
-                tag: Some("div"),
-                pseudo,
+                tag: Some(tag),
+                qualifiers,
                 ..
-            })) if pseudo.len() == 1 && matches!(pseudo[0], Pseudo{property: "hover", value: None, mode: PseudoMode::PseudoClass })
+            })) if tag == "div" && matches!(&qualifiers[..], [Qualifier::Pseudo(x)] if matches!(**x, Pseudo{ ref property, value: None, mode: PseudoMode::PseudoClass, .. } if property == "hover"))
         )
     }
 
     #[test]
     fn test_parameterized_pesudo() {
         assert_matches!(
-            SelectorTerm::parse::<()>("div:not(.test)"),
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("div:not(.test)"),
             Ok(("", SelectorTerm {
-                tag: Some("div"),
-                pseudo,
+                tag: Some(tag),
+                qualifiers,
                 ..
-            })) if pseudo.len() == 1 && matches!(pseudo[0], Pseudo{ property: "not", value: Some(_), mode: PseudoMode::PseudoClass })
+            })) if tag == "div" && matches!(&qualifiers[..], [Qualifier::Pseudo(x)] if matches!(**x, Pseudo{ ref property, value: Some(_), mode: PseudoMode::PseudoClass, .. } if property == "not"))
         )
     }
 
     #[test]
     fn test_parameterized_pesudo_nth_child() {
         assert_matches!(
-            SelectorTerm::parse::<()>("div:nth-child(2)"),
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("div:nth-child(2)"),
             Ok(("", SelectorTerm {
-                tag: Some("div"),
-                pseudo,
+                tag: Some(tag),
+                qualifiers,
                 ..
-            })) if pseudo.len() == 1 && matches!(pseudo[0], Pseudo{ property: "nth-child", value: Some(_), mode: PseudoMode::PseudoClass })
+            })) if tag == "div" && matches!(&qualifiers[..], [Qualifier::Pseudo(x)] if matches!(**x, Pseudo{ ref property, value: Some(_), mode: PseudoMode::PseudoClass, .. } if property == "nth-child"))
         )
     }
 
     #[test]
     fn test_parameterized_pesudo_renders_correctly() {
         assert_matches!(
-            SelectorTerm::<Option<&str>>::parse::<winnow::error::VerboseError<&str>>(
+            SelectorTerm::<Option<Cow<str>>>::parse::<CssError>(
                 "div:nth-child(2)"
             )
             .map(|x| x.as_css_string())
@@ -354,19 +892,19 @@ mod tests {
     #[test]
     fn test_pesudo_element() {
         assert_matches!(
-            SelectorTerm::parse::<()>("div::-webkit-scroll-thumb"),
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("div::-webkit-scroll-thumb"),
             Ok(("", SelectorTerm {
-                tag: Some("div"),
-                pseudo,
+                tag: Some(tag),
+                qualifiers,
                 ..
-            })) if pseudo.len() == 1 && matches!(pseudo[0], Pseudo{property: "-webkit-scroll-thumb", value: None, mode: PseudoMode::PseudoElement })
+            })) if tag == "div" && matches!(&qualifiers[..], [Qualifier::Pseudo(x)] if matches!(**x, Pseudo{ ref property, value: None, mode: PseudoMode::PseudoElement, .. } if property == "-webkit-scroll-thumb"))
         )
     }
 
     #[test]
     fn test_pesudo_element_renders_correctly() {
         assert_matches!(
-            SelectorTerm::<Option<&str>>::parse::<winnow::error::VerboseError<&str>>(
+            SelectorTerm::<Option<Cow<str>>>::parse::<CssError>(
                 "div::-webkit-scroll-thumb"
             )
             .map(|x| x.as_css_string())
@@ -374,4 +912,123 @@ mod tests {
             Ok("div::-webkit-scroll-thumb")
         )
     }
+
+    #[test]
+    fn test_pseudo_selector_list() {
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("a:not(.b, div > p)")
+                .map(|x| x.1.as_css_string())
+                .as_deref(),
+            Ok("a:not(.b,div>p)")
+        );
+
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>(":where( .a )"),
+            Ok(("", SelectorTerm {
+                qualifiers,
+                ..
+            })) if matches!(&qualifiers[..], [Qualifier::Pseudo(x)] if matches!(&x.value, Some(PseudoArg::Selector(x)) if x.iter().count() == 1))
+        )
+    }
+
+    #[test]
+    fn test_pseudo_relative() {
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("a:has(> img, p)")
+                .map(|x| x.1.as_css_string())
+                .as_deref(),
+            Ok("a:has(>img,p)")
+        )
+    }
+
+    #[test]
+    fn test_pseudo_nth() {
+        for (input, a, b) in [
+            ("2n+1", 2, 1),
+            ("odd", 2, 1),
+            ("even", 2, 0),
+            ("-n + 3", -1, 3),
+            ("n", 1, 0),
+            ("-2", 0, -2),
+            ("3n-1", 3, -1),
+        ] {
+            let input = format!(":nth-child({})", input);
+            assert_matches!(
+                SelectorTerm::<Option<Cow<str>>>::parse::<()>(&input),
+                Ok(("", SelectorTerm {
+                    qualifiers,
+                    ..
+                })) if matches!(&qualifiers[..], [Qualifier::Pseudo(x)] if matches!(&x.value, Some(PseudoArg::Nth(x)) if x.a == a && x.b == b))
+            );
+        }
+
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("li:nth-child( 2n + 1 of .row )")
+                .map(|x| x.1.as_css_string())
+                .as_deref(),
+            Ok("li:nth-child(2n+1 of .row)")
+        )
+    }
+
+    #[test]
+    fn test_pseudo_raw() {
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("p:lang(\"en\")::part(a (b))")
+                .map(|x| x.1.as_css_string())
+                .as_deref(),
+            Ok("p:lang(\"en\")::part(a (b))")
+        )
+    }
+
+    #[test]
+    fn test_universal() {
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("*.a"),
+            Ok(("", SelectorTerm {
+                tag: Some(tag),
+                namespace: None,
+                ..
+            })) if tag == "*"
+        );
+
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("div*"),
+            Ok(("*", _))
+        )
+    }
+
+    #[test]
+    fn test_namespace() {
+        for (input, namespace, tag) in [
+            ("svg|rect", "svg", "rect"),
+            ("*|div", "*", "div"),
+            ("ns|*", "ns", "*"),
+            ("|a", "", "a"),
+        ] {
+            assert_matches!(
+                SelectorTerm::<Option<Cow<str>>>::parse::<()>(input),
+                Ok(("", SelectorTerm {
+                    namespace: Some(x),
+                    tag: Some(y),
+                    ..
+                })) if x == namespace && y == tag
+            );
+
+            assert_matches!(
+                SelectorTerm::<Option<Cow<str>>>::parse::<()>(input)
+                    .map(|x| x.1.as_css_string())
+                    .as_deref(),
+                Ok(x) if x == input
+            );
+        }
+
+        assert_matches!(
+            SelectorTerm::<Option<Cow<str>>>::parse::<()>("col||td"),
+            Ok(("||td", SelectorTerm {
+                namespace: None,
+                tag: Some(tag),
+                ..
+            })) if tag == "col"
+        )
+    }
 }
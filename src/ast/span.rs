@@ -0,0 +1,167 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use std::hash::{Hash, Hasher};
+
+use crate::transform::TransformCss;
+
+/// A location in the source text.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// Byte offset from the start of the source.
+    pub offset: usize,
+
+    /// Line number, starting from 1.
+    pub line: usize,
+
+    /// Column number in `char`s, starting from 1.
+    pub column: usize,
+}
+
+/// The region of source text an AST node was parsed from.  Nodes which were
+/// created by a transform rather than parsed have a default (empty) [`Span`].
+///
+/// Spans are ignored by `==` and [`Hash`] (every [`Span`] is equal to every
+/// other), such that nodes parsed from different locations still compare by
+/// their content.  Compare the `start` and `end` fields to compare locations.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl PartialEq for Span {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Span {}
+
+impl Hash for Span {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl Span {
+    /// The span of the input consumed by a parser which started at `input` and
+    /// stopped at `rest`.  Parsers only ever see a suffix of the source, so
+    /// the offsets are recorded from the _end_ of the source until the full
+    /// tree is parsed, and [`Span::resolve`] is called.
+    pub(crate) fn parsed(input: &str, rest: &str) -> Self {
+        Span {
+            start: Position {
+                offset: input.len(),
+                ..Position::default()
+            },
+            end: Position {
+                offset: rest.len(),
+                ..Position::default()
+            },
+        }
+    }
+
+    /// Convert a [`Span::parsed`] into offsets, lines and columns from the
+    /// start of the source.
+    pub(crate) fn resolve(&mut self, index: &LineIndex<'_>) {
+        self.start = index.position(index.src.len() - self.start.offset);
+        self.end = index.position(index.src.len() - self.end.offset);
+    }
+
+    /// A [`Span`] from the start of `self` to the end of `other`.
+    pub fn join(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+
+    /// The slice of `src` this [`Span`] covers, if `src` is the source this
+    /// [`Span`] was parsed from.
+    pub fn as_str<'a>(&self, src: &'a str) -> Option<&'a str> {
+        src.get(self.start.offset..self.end.offset)
+    }
+}
+
+impl TransformCss<Span> for Span {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        f(self)
+    }
+}
+
+/// A lookup table from byte offsets to lines and columns of a source text.
+pub(crate) struct LineIndex<'a> {
+    src: &'a str,
+    lines: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(src: &'a str) -> Self {
+        let lines = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        LineIndex { src, lines }
+    }
+
+    /// The byte offset of the start of the line containing `offset`.
+    pub(crate) fn line_start(&self, offset: usize) -> usize {
+        self.lines[self.line(offset)]
+    }
+
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let line = self.line(offset);
+        let line_start = self.lines[line];
+        let column = self
+            .src
+            .get(line_start..offset)
+            .map(|x| x.chars().count())
+            .unwrap_or(offset - line_start);
+
+        Position {
+            offset,
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+
+    fn line(&self, offset: usize) -> usize {
+        self.lines.partition_point(|x| *x <= offset) - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let index = LineIndex::new("div {\n  color: red;\n}");
+        assert_eq!(index.position(0), Position {
+            offset: 0,
+            line: 1,
+            column: 1
+        });
+
+        assert_eq!(index.position(8), Position {
+            offset: 8,
+            line: 2,
+            column: 3
+        });
+
+        assert_eq!(index.position(20), Position {
+            offset: 20,
+            line: 3,
+            column: 1
+        });
+    }
+}
@@ -1,135 +1,278 @@
-use std::{any, borrow::Cow};
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use std::borrow::Cow;
 
 use winnow::{
-    ascii::{alphanumeric0, hex_digit1},
-    combinator::{alt, dispatch, fail, not, opt, preceded, repeat, repeat_till0, success},
-    stream::{AsChar, Stream},
-    token::{any, take_while},
-    PResult, Parser,
+    combinator::{alt, opt, preceded, repeat},
+    error::ParserError,
+    stream::AsChar,
+    token::{none_of, one_of, take_while},
+    IResult, PResult, Parser,
+};
+
+use crate::{
+    parser::{ParseCss, ParseCssError},
+    render::RenderCss,
 };
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+/// An identifier, per
+/// [CSS Syntax](https://www.w3.org/TR/css-syntax-3/#ident-token-diagram),
+/// as written in the source (escapes included).  Selectors, properties and
+/// at-rule names are parsed as identifiers, and rendered with their escapes
+/// intact; use [`Ident::unescape`] to compare them by value.
+///
+/// ```
+/// use procss::ast::Ident;
+/// let ident = Ident::new("sm\\:flex");
+/// assert_eq!(ident.as_str(), "sm\\:flex");
+/// assert_eq!(ident.unescape(), "sm:flex");
+/// assert_eq!(Ident::new("\\31 23").unescape(), "123");
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct Ident<'a> {
     data: Cow<'a, str>,
 }
 
 impl<'a> Ident<'a> {
-    fn is_valid_start_char(c: char) -> bool {
-        c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+    /// Create an [`Ident`] from its escaped text, which is not validated.
+    pub fn new(data: impl Into<Cow<'a, str>>) -> Self {
+        Ident { data: data.into() }
     }
 
-    fn is_valid_char(c: char) -> bool {
-        c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+    /// The text of this identifier, as written (escapes included).
+    pub fn as_str(&self) -> &str {
+        &self.data
     }
 
-    fn eat_char(input: &mut &'a str) -> PResult<&'a str> {
-        let empty = "";
-        dispatch! {any;
-            '\\' => parse_escaped_char,
-            c if Self::is_valid_char(c) => {success(empty)},
-            _ => fail::<_, &str, _>
-        }
-        .recognize()
-        .parse_next(input)
+    /// The text of this identifier with its escapes replaced by the
+    /// characters they represent, e.g. `sm:flex` for `sm\:flex`.  This only
+    /// allocates when the identifier contains an escape.
+    pub fn unescape(&self) -> Cow<'_, str> {
+        unescape(&self.data)
     }
+}
+
+impl<'a> ParseCss<'a> for Ident<'a> {
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        parse_ident.map(Ident::new).parse_peek(input)
+    }
+}
 
-    pub fn parse(input: &mut &'a str) -> PResult<Self> {
-        (
-            alt((
-                ("--"),
-                ("-", take_while(1..=1, Self::is_valid_start_char)).recognize(),
-                take_while(1..=1, Self::is_valid_start_char),
-            )),
-            repeat::<_, _, (), _, _>(0.., Self::eat_char),
-        )
-            .recognize()
-            .map(|data: &'a str| Self { data: data.into() })
-            .parse_next(input)
+impl<'a> RenderCss for Ident<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.data)
     }
 }
 
-fn hex_digit_token<'a>(input: &mut &'a str) -> PResult<&'a str> {
-    (take_while(1..=6, AsChar::is_hex_digit), opt(" "))
+fn is_name_start_char(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+/// An escape, e.g. `\:` or `\31 ` (a hex code point may be followed by a
+/// single whitespace, which is part of the escape).
+fn parse_escape<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    preceded(
+        '\\',
+        alt((
+            (
+                take_while(1..=6, AsChar::is_hex_digit),
+                opt(alt((
+                    "\r\n",
+                    one_of([' ', '\t', '\n', '\r', '\x0c']).recognize(),
+                ))),
+            )
+                .void(),
+            none_of(['\n', '\r', '\x0c']).void(),
+        )),
+    )
+    .recognize()
+    .parse_next(input)
+}
+
+/// A sequence of name characters and escapes, which unlike [`parse_ident`]
+/// may start with a digit, e.g. the `123` in `#123`.
+pub(crate) fn parse_name<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    repeat::<_, _, (), _, _>(1.., alt((one_of(is_name_char).void(), parse_escape.void())))
         .recognize()
         .parse_next(input)
 }
 
-fn any_char<'a>(input: &mut &'a str) -> PResult<&'a str> {
-    let src = *input;
-    not((' ', '\r', '\t', '\n')).recognize().parse_next(input)
+/// An identifier, e.g. `div`, `--color` or `sm\:flex`.  See [`Ident`].
+pub(crate) fn parse_ident<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    (
+        alt((
+            "--".void(),
+            (
+                opt('-'),
+                alt((one_of(is_name_start_char).void(), parse_escape.void())),
+            )
+                .void(),
+        )),
+        opt(parse_name),
+    )
+        .recognize()
+        .parse_next(input)
 }
 
-fn parse_escaped_char<'a>(input: &mut &'a str) -> PResult<&'a str> {
-    let char_parser = alt((hex_digit_token, any_char));
-    char_parser.recognize().parse_next(input)
+/// Replace the escapes in `input` with the characters they represent.
+/// Invalid code points are replaced with `U+FFFD`.
+fn unescape(input: &str) -> Cow<'_, str> {
+    if !input.contains('\\') {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+
+        let mut hex = String::new();
+        while hex.len() < 6 {
+            match chars.peek() {
+                Some(x) if x.is_ascii_hexdigit() => {
+                    hex.push(*x);
+                    chars.next();
+                }
+                _ => break,
+            }
+        }
+
+        if hex.is_empty() {
+            output.push(chars.next().unwrap_or(char::REPLACEMENT_CHARACTER));
+            continue;
+        }
+
+        match chars.peek() {
+            Some('\r') => {
+                chars.next();
+                chars.next_if_eq(&'\n');
+            }
+            Some(' ' | '\t' | '\n' | '\x0c') => {
+                chars.next();
+            }
+            _ => (),
+        }
+
+        let code = u32::from_str_radix(&hex, 16).unwrap_or(0);
+        output.push(
+            char::from_u32(code)
+                .filter(|x| *x != '\0')
+                .unwrap_or(char::REPLACEMENT_CHARACTER),
+        );
+    }
+
+    Cow::Owned(output)
 }
 
 #[cfg(test)]
 mod test {
-    use winnow::Parser;
-
     use super::Ident;
+    use crate::parser::ParseCss;
 
     #[test]
     pub fn test_valid_ident() {
         let input = "regular";
-        let (rest, ident) = Ident::parse.parse_peek(input).unwrap();
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
         assert_eq!(rest, "");
         assert_eq!(ident.data, "regular");
 
         let input = "--fo-oA21f:bar";
-        let (rest, ident) = Ident::parse.parse_peek(input).unwrap();
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
         assert_eq!(rest, ":bar");
         assert_eq!(ident.data, "--fo-oA21f");
 
         let input = "--23";
-        let (rest, ident) = Ident::parse.parse_peek(input).unwrap();
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
         assert_eq!(rest, "");
         assert_eq!(ident.data, "--23");
 
         let input = "-fo-bar";
-        let (rest, ident) = Ident::parse.parse_peek(input).unwrap();
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
         assert_eq!(rest, "");
         assert_eq!(ident.data, "-fo-bar");
 
         let input = "selector {asda}";
-        let (rest, ident) = Ident::parse.parse_peek(input).unwrap();
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
         assert_eq!(rest, " {asda}");
         assert_eq!(ident.data, "selector");
 
         let input = "attr[xasd]";
-        let (rest, ident) = Ident::parse.parse_peek(input).unwrap();
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
         assert_eq!(rest, "[xasd]");
         assert_eq!(ident.data, "attr");
 
         let input = "funct(foo)";
-        let (rest, ident) = Ident::parse.parse_peek(input).unwrap();
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
         assert_eq!(rest, "(foo)");
         assert_eq!(ident.data, "funct");
+
+        let input = "größe:1";
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
+        assert_eq!(rest, ":1");
+        assert_eq!(ident.data, "größe");
     }
 
     #[test]
     pub fn test_valid_escape_ident() {
         let input = "foo\\nn ";
 
-        let (rest, ident) = Ident::parse.parse_peek(input).unwrap();
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
         assert_eq!(ident.data, "foo\\nn");
         assert_eq!(rest, " ");
 
         let input = "foo\\AA ss ";
 
-        let (rest, ident) = Ident::parse.parse_peek(input).unwrap();
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
         assert_eq!(ident.data, "foo\\AA ss");
         assert_eq!(rest, " ");
+
+        let input = "\\31 23 {";
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
+        assert_eq!(ident.data, "\\31 23");
+        assert_eq!(rest, " {");
+
+        let input = "w-1\\/2.x";
+        let (rest, ident) = Ident::parse::<()>(input).unwrap();
+        assert_eq!(ident.data, "w-1\\/2");
+        assert_eq!(rest, ".x");
     }
 
     #[test]
     pub fn test_invalid_ident() {
         let input = "2-digits";
-        let err = Ident::parse.parse_peek(input).unwrap_err();
-        // panic!("{:?}", err)
+        assert!(Ident::parse::<()>(input).is_err());
 
         let input = "-2-digits";
-        let err = Ident::parse.parse_peek(input).unwrap_err();
+        assert!(Ident::parse::<()>(input).is_err());
+
+        let input = "\\\nfoo";
+        assert!(Ident::parse::<()>(input).is_err());
+    }
+
+    #[test]
+    pub fn test_unescape() {
+        assert_eq!(Ident::new("regular").unescape(), "regular");
+        assert_eq!(Ident::new("foo\\nn").unescape(), "foonn");
+        assert_eq!(Ident::new("foo\\AA ss").unescape(), "foo\u{aa}ss");
+        assert_eq!(Ident::new("\\31 23").unescape(), "123");
+        assert_eq!(Ident::new("w-1\\/2").unescape(), "w-1/2");
+        assert_eq!(Ident::new("a\\0 b").unescape(), "a\u{fffd}b");
+        assert_eq!(Ident::new("a\\110000").unescape(), "a\u{fffd}");
     }
 }
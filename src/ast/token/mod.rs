@@ -12,8 +12,8 @@
 mod ident;
 mod space;
 mod string;
-mod symbol;
 
+pub use ident::Ident;
+pub(crate) use ident::{parse_ident, parse_name};
 pub(crate) use space::*;
 pub(crate) use string::*;
-pub(crate) use symbol::*;
@@ -51,11 +51,13 @@ impl NeedsWhitespaceStringExt for str {
 }
 
 /// Render `s` trimming all intermediate whitespace to a single character along
-/// the way.
+/// the way.  Whitespace between a word and `(` is kept, as without it they
+/// would be a function, e.g. `screen and (color)`.  Quoted strings are
+/// written as-is.
 pub fn trim_whitespace(s: &str, f: &mut std::fmt::Formatter<'_>) {
     let mut last_alpha = false;
-    s.split_whitespace().for_each(|w| {
-        if last_alpha && w.needs_pre_ws() {
+    split_unquoted_whitespace(s).for_each(|w| {
+        if last_alpha && (w.needs_pre_ws() || w.starts_with('(')) {
             write!(f, " ").unwrap();
         }
 
@@ -64,6 +66,40 @@ pub fn trim_whitespace(s: &str, f: &mut std::fmt::Formatter<'_>) {
     });
 }
 
+/// Split `s` on whitespace like [`str::split_whitespace`], except for
+/// whitespace inside a `"` or `'` quoted string.
+fn split_unquoted_whitespace(s: &str) -> impl Iterator<Item = &str> {
+    let mut rest = s;
+    std::iter::from_fn(move || {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+
+        let mut quote = None;
+        let mut escaped = false;
+        let mut end = rest.len();
+        for (idx, c) in rest.char_indices() {
+            match quote {
+                Some(_) if escaped => escaped = false,
+                Some(_) if c == '\\' => escaped = true,
+                Some(q) if c == q => quote = None,
+                Some(_) => (),
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if c.is_whitespace() => {
+                    end = idx;
+                    break;
+                }
+                None => (),
+            }
+        }
+
+        let (word, next) = rest.split_at(end);
+        rest = next;
+        Some(word)
+    })
+}
+
 // pub fn trim_whitespace(s: &str, f: &mut std::fmt::Formatter<'_>) {
 //     let mut flag = false;
 //     s.split_whitespace().for_each(|w| {
@@ -122,26 +158,16 @@ where
     Ok(())
 }
 
-/// Parses 1 or more whitespace characters, including comments.
-pub fn comment1<'a, E>(input: &mut &'a str) -> PResult<(), E>
-where
-    E: ParserError<&'a str>,
-{
-    ignore(repeat::<_, _, Vec<_>, _, _>(
-        1..,
-        alt((ignore(multispace1), parse_comment, parse_multi_comment)),
-    ))
-    .parse_next(input)
-}
-
-/// Parses 0 or more whitespace characters, including comments and semicolons.
+/// Parses 0 or more whitespace characters, `//` comments and semicolons,
+/// stopping at `/* */` comments so they can be parsed as
+/// [`crate::ast::Comment`] nodes.
 pub fn sep0<'a, E>(input: &mut &'a str) -> PResult<(), E>
 where
     E: ParserError<&'a str>,
 {
     ignore(repeat::<_, _, Vec<_>, _, _>(
         0..,
-        alt((comment1, ignore(tag(";")))),
+        alt((ignore(multispace1), parse_comment, ignore(tag(";")))),
     ))
     .parse_next(input)
 }
@@ -170,6 +196,14 @@ mod tests {
         assert_matches!(comment0::<()>.parse_peek("// test"), Ok(("", ())))
     }
 
+    #[test]
+    fn test_sep_stops_at_comment() {
+        assert_matches!(
+            sep0::<()>.parse_peek(" ;// test\n /* test */"),
+            Ok(("/* test */", ()))
+        )
+    }
+
     #[test]
     fn test_semicolons() {
         assert_matches!(comment0::<()>.parse_peek("/* test; test */"), Ok(("", ())))
@@ -10,54 +10,60 @@
 // └───────────────────────────────────────────────────────────────────────────┘
 
 use winnow::{
-    combinator::{alt, delimited, fold_repeat, not, preceded},
-    error::{ErrorKind, ParserError},
-    token::take_till1,
-    IResult, PResult, Parser,
+    combinator::{alt, opt, preceded, repeat},
+    error::ParserError,
+    token::{any, one_of, take_till1, take_while},
+    PResult, Parser,
 };
 
-fn parse_escaped_char<'a>(input: &mut &'a str) -> PResult<&'a str> {
-    preceded('\\', take_till1((' ', '\r', '\t', '\n', '\"')))
+/// An escape sequence, either `\` followed by 1 to 6 hex digits and an
+/// optional whitespace character, or `\` followed by any other character.
+fn parse_escaped_char<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    let hex = (
+        take_while(1..=6, |c: char| c.is_ascii_hexdigit()),
+        opt(one_of([' ', '\t', '\n', '\r', '\x0c'])),
+    );
+
+    preceded('\\', alt((hex.void(), any.void())))
         .recognize()
         .parse_next(input)
 }
 
-fn parse_literal<'a>(input: &mut &'a str) -> PResult<&'a str> {
-    let not_quote_slash = take_till1(('\"', '\\'));
-    not_quote_slash
-        .verify(|s: &str| !s.is_empty())
-        .parse_next(input)
+fn parse_quoted<'a, E: ParserError<&'a str>>(quote: char) -> impl Parser<&'a str, &'a str, E> {
+    let fragment = alt((take_till1([quote, '\\']), parse_escaped_char));
+    (quote, repeat::<_, _, (), _, _>(0.., fragment), quote).recognize()
 }
 
-enum StringFragment {
-    Literal(usize),
-    EscapedChar(usize),
+/// Parses a `"` or `'` quoted string literal, returning the literal including
+/// its quotes and with its escapes intact.
+pub fn parse_string_literal<'a, E: ParserError<&'a str>>() -> impl Parser<&'a str, &'a str, E> {
+    alt((parse_quoted('"'), parse_quoted('\'')))
 }
 
-impl StringFragment {
-    fn len(&self) -> usize {
-        match self {
-            StringFragment::Literal(s) => *s,
-            StringFragment::EscapedChar(s) => *s,
-        }
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn test_single_quoted() {
+        assert_matches!(
+            parse_string_literal::<()>().parse_peek("': test ; alpha' rest"),
+            Ok((" rest", "': test ; alpha'"))
+        )
     }
-}
 
-fn parse_fragment<'a>(input: &mut &'a str) -> PResult<StringFragment> {
-    alt((
-        parse_literal.map(|x| StringFragment::Literal(x.len())),
-        parse_escaped_char.map(|x| StringFragment::EscapedChar(x.len())),
-    ))
-    .parse_next(input)
-}
+    #[test]
+    fn test_escaped_quote() {
+        assert_matches!(
+            parse_string_literal::<()>().parse_peek(r#"'it\'s' "\"\1234 x""#),
+            Ok((r#" "\"\1234 x""#, r#"'it\'s'"#))
+        );
 
-pub fn parse_string_literal<'a, E: ParserError<&'a str>>() -> impl Parser<&'a str, &'a str, E> {
-    move |input: &mut &'a str| {
-        let build_string = fold_repeat(0.., parse_fragment, || 2, |len, frag| frag.len() + len);
-        let offset = delimited('"', build_string, '"');
-        let res = offset.map(|x| &input[..x]).parse_next(input);
-        res.map_err(|_| {
-            winnow::error::ErrMode::Backtrack(E::from_error_kind(&input, ErrorKind::Slice))
-        })
+        assert_matches!(
+            parse_string_literal::<()>().parse_peek(r#""\"\1234 x""#),
+            Ok(("", r#""\"\1234 x""#))
+        )
     }
 }
@@ -10,34 +10,74 @@
 // └───────────────────────────────────────────────────────────────────────────┘
 
 use winnow::{
-    combinator::{alt, peek, repeat, terminated},
-    error::ParserError,
+    ascii::multispace1,
+    combinator::{alt, cut_err, delimited, not, opt, peek, repeat, terminated},
+    error::{AddContext, ErrMode, ErrorKind, ParserError},
     token::tag,
-    unpeek, IResult, Parser,
+    unpeek, IResult, PResult, Parser,
 };
 
 use super::{
-    flat_ruleset::FlatRuleset,
-    ruleset::{QualNestedRuleset, QualRule, QualRuleset, Rule, Ruleset, SelectorRuleset},
+    flat_ruleset::{FlatRule, FlatRuleset},
+    ruleset::{
+        AtRule, Comment, Keyframe, KeyframesRuleset, QualNestedRuleset, QualRule, QualRuleset,
+        Rule, Ruleset, SelectorRuleset,
+    },
     selector::Selector,
+    span::Span,
     token::{comment0, sep0},
 };
-use crate::{parser::*, render::*, transform::TransformCss};
+use crate::{owned::IntoOwned, parser::*, render::*, transform::TransformCss};
 
 /// A tree node which expresses a recursive `T` over `Ruleset<T>`.  Using this
 /// struct in place of `Rule` allows nested CSS selectors that can be later
 /// flattened.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TreeRule<'a> {
     Rule(Rule<'a>),
-    Ruleset(TreeRuleset<'a>),
+    Ruleset(Box<TreeRuleset<'a>>),
+    Comment(Comment<'a>),
 }
 
 impl<'a> ParseCss<'a> for TreeRule<'a> {
-    fn parse<E: ParserError<&'a str>>(input: &'a str) -> IResult<&'a str, Self, E> {
-        let block = terminated(unpeek(TreeRuleset::parse), sep0).map(TreeRule::Ruleset);
-        let rule = terminated(unpeek(Rule::parse), sep0).map(TreeRule::Rule);
-        alt((block, rule)).parse_peek(input)
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        let comment = terminated(unpeek(Comment::parse), sep0).map(TreeRule::Comment);
+        let block = terminated(unpeek(TreeRuleset::parse), sep0).map(TreeRule::from);
+        let rule = unpeek(|input| -> IResult<&'a str, _, E> {
+            let (rest, rule) = Rule::parse(input)?;
+            let (rest, _) = cut_err(peek(alt((";", "}"))))
+                .parse_peek(rest)
+                .map_err(|err| err.add_context(&input, "`;` or `}` after declaration"))?;
+
+            Ok((rest, TreeRule::Rule(rule)))
+        });
+
+        // A custom property may look like a nested selector, e.g. `--x:hover {}`,
+        // so declarations starting with `--` take precedence.
+        if input.starts_with("--") {
+            alt((terminated(rule, sep0), block)).parse_peek(input)
+        } else {
+            alt((comment, block, terminated(rule, sep0))).parse_peek(input)
+        }
+    }
+}
+
+impl<'a> From<Rule<'a>> for TreeRule<'a> {
+    fn from(rule: Rule<'a>) -> Self {
+        TreeRule::Rule(rule)
+    }
+}
+
+impl<'a> From<TreeRuleset<'a>> for TreeRule<'a> {
+    fn from(ruleset: TreeRuleset<'a>) -> Self {
+        TreeRule::Ruleset(Box::new(ruleset))
+    }
+}
+
+impl<'a> From<Comment<'a>> for TreeRule<'a> {
+    fn from(comment: Comment<'a>) -> Self {
+        TreeRule::Comment(comment)
     }
 }
 
@@ -46,6 +86,19 @@ impl<'a> RenderCss for TreeRule<'a> {
         match self {
             TreeRule::Rule(rule) => rule.render(f),
             TreeRule::Ruleset(block) => block.render(f),
+            TreeRule::Comment(comment) => comment.render(f),
+        }
+    }
+}
+
+impl<'a> IntoOwned for TreeRule<'a> {
+    type Owned = TreeRule<'static>;
+
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            TreeRule::Rule(rule) => TreeRule::Rule(rule.into_owned()),
+            TreeRule::Ruleset(ruleset) => TreeRule::Ruleset(Box::new((*ruleset).into_owned())),
+            TreeRule::Comment(comment) => TreeRule::Comment(comment.into_owned()),
         }
     }
 }
@@ -55,6 +108,27 @@ impl<'a> TransformCss<Rule<'a>> for TreeRule<'a> {
         match self {
             TreeRule::Rule(rule) => f(rule),
             TreeRule::Ruleset(ruleset) => ruleset.transform_each(f),
+            TreeRule::Comment(_) => (),
+        }
+    }
+}
+
+impl<'a> TransformCss<Span> for TreeRule<'a> {
+    fn transform_each<F: FnMut(&mut Span)>(&mut self, f: &mut F) {
+        match self {
+            TreeRule::Rule(rule) => rule.transform_each(f),
+            TreeRule::Ruleset(ruleset) => ruleset.transform_each(f),
+            TreeRule::Comment(comment) => comment.transform_each(f),
+        }
+    }
+}
+
+impl<'a> TransformCss<Comment<'a>> for TreeRule<'a> {
+    fn transform_each<F: FnMut(&mut Comment<'a>)>(&mut self, f: &mut F) {
+        match self {
+            TreeRule::Rule(_) => (),
+            TreeRule::Ruleset(ruleset) => ruleset.transform_each(f),
+            TreeRule::Comment(comment) => f(comment),
         }
     }
 }
@@ -64,6 +138,7 @@ impl<'a> TransformCss<TreeRuleset<'a>> for TreeRule<'a> {
         match self {
             TreeRule::Rule(_) => (),
             TreeRule::Ruleset(ruleset) => ruleset.transform_each(f),
+            TreeRule::Comment(_) => (),
         }
     }
 }
@@ -73,6 +148,7 @@ impl<'a> TransformCss<Vec<TreeRuleset<'a>>> for TreeRule<'a> {
         match self {
             TreeRule::Rule(_) => (),
             TreeRule::Ruleset(ruleset) => ruleset.transform_each(f),
+            TreeRule::Comment(_) => (),
         }
     }
 }
@@ -94,16 +170,48 @@ impl<'a> TransformCss<Vec<TreeRuleset<'a>>> for TreeRule<'a> {
 pub type TreeRuleset<'a> = Ruleset<'a, TreeRule<'a>>;
 
 impl<'a> ParseCss<'a> for TreeRuleset<'a> {
-    fn parse<E: ParserError<&'a str>>(input: &'a str) -> IResult<&'a str, Self, E> {
-        if let Ok((input, _)) = peek::<_, _, E, _>("@").parse_peek(input) {
-            let (input, qual_rule) = QualRule::parse(input)?;
-            if let Ok((input, _)) = tag::<_, _, E>(";").parse_peek(input) {
-                Ok((input, Ruleset::QualRule(qual_rule)))
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        if let Ok((rest, comment)) = Comment::parse::<E>(input) {
+            Ok((rest, Ruleset::Comment(comment)))
+        } else if let Ok((rest, _)) = peek::<_, _, E, _>((not(nest_prefix), "@")).parse_peek(input)
+        {
+            let (rest, qual_rule) = QualRule::parse(rest)?;
+            let (rest, term) = cut_err(alt((";", "{")))
+                .parse_peek(rest)
+                .map_err(|err| err.add_context(&input, "`;` or `{` after at-rule"))?;
+
+            if term == ";" {
+                Ok((rest, Ruleset::QualRule(qual_rule)))
+            } else if matches!(qual_rule.at_rule(), AtRule::Keyframes(_)) {
+                let (rest, _) = sep0.parse_peek(rest)?;
+                let (rest, keyframes) =
+                    repeat(0.., delimited(comment0, unpeek(Keyframe::parse), sep0))
+                        .parse_peek(rest)?;
+
+                let (rest, _) = cut_err((comment0, tag("}")))
+                    .context("keyframe selector or `}`")
+                    .parse_peek(rest)?;
+
+                let span = Span::parsed(input, rest);
+                Ok((
+                    rest,
+                    Ruleset::Keyframes(KeyframesRuleset(qual_rule, keyframes, span)),
+                ))
             } else {
-                let (input, _) = (tag("{"), sep0).parse_peek(input)?;
-                let (input, rules) = repeat(1.., unpeek(TreeRule::parse::<E>)).parse_peek(input)?;
-                let (input, _) = (comment0, tag("}")).parse_peek(input)?;
-                Ok((input, Ruleset::QualRuleset(QualRuleset(qual_rule, rules))))
+                let (rest, _) = sep0.parse_peek(rest)?;
+                let (rest, rules) = cut_err(repeat(1.., unpeek(TreeRule::parse::<E>)))
+                    .context("declaration or ruleset")
+                    .parse_peek(rest)?;
+
+                let (rest, _) = cut_err((comment0, tag("}")))
+                    .context("declaration, ruleset or `}`")
+                    .parse_peek(rest)?;
+
+                let span = Span::parsed(input, rest);
+                Ok((
+                    rest,
+                    Ruleset::QualRuleset(QualRuleset(qual_rule, rules, span)),
+                ))
             }
         } else {
             let (input, selector_ruleset) = SelectorRuleset::parse(input)?;
@@ -119,6 +227,8 @@ impl<'a> TransformCss<TreeRuleset<'a>> for TreeRuleset<'a> {
             Ruleset::QualRule(_) => (),
             Ruleset::QualRuleset(_) => (),
             Ruleset::QualNestedRuleset(..) => (),
+            Ruleset::Keyframes(_) => (),
+            Ruleset::Comment(_) => (),
             Ruleset::SelectorRuleset(ruleset) => {
                 for rule in ruleset.1.iter_mut() {
                     rule.transform_each(f)
@@ -136,22 +246,44 @@ impl<'a> TreeRuleset<'a> {
         match self {
             Ruleset::SelectorRuleset(ruleset) => ruleset.flatten_tree(),
             Ruleset::QualRule(x) => vec![Ruleset::QualRule(x.clone())],
+            Ruleset::Comment(x) => vec![Ruleset::Comment(x.clone())],
+            Ruleset::Keyframes(x) => vec![Ruleset::Keyframes(x.clone())],
             Ruleset::QualRuleset(rules) => {
-                let mut new_rules: Vec<Rule<'a>> = vec![];
+                let mut new_rules: Vec<FlatRule<'a>> = vec![];
                 let mut new_rulesets: Vec<FlatRuleset<'a>> = vec![];
+                let mut comments: Vec<Comment<'a>> = vec![];
                 for rule in rules.1.iter() {
                     match rule {
-                        TreeRule::Rule(rule) => new_rules.push(rule.clone()),
+                        TreeRule::Comment(x) => comments.push(x.clone()),
+                        TreeRule::Rule(rule) => {
+                            new_rules.extend(comments.drain(..).map(FlatRule::Comment));
+                            new_rules.push(FlatRule::Rule(rule.clone()))
+                        }
                         TreeRule::Ruleset(ruleset) => {
+                            new_rulesets.extend(comments.drain(..).map(Ruleset::Comment));
                             let sub_rules = ruleset.flatten_tree().into_iter();
                             new_rulesets.extend(sub_rules)
                         }
                     }
                 }
 
+                // Comments stay in the same block as the rule or ruleset they
+                // precede, and trailing comments with the last one.
+                match rules
+                    .1
+                    .iter()
+                    .rev()
+                    .find(|x| !matches!(x, TreeRule::Comment(_)))
+                {
+                    Some(TreeRule::Ruleset(_)) => {
+                        new_rulesets.extend(comments.into_iter().map(Ruleset::Comment))
+                    }
+                    _ => new_rules.extend(comments.into_iter().map(FlatRule::Comment)),
+                }
+
                 let mut ret = vec![];
                 if !new_rules.is_empty() {
-                    let ruleset = QualRuleset(rules.0.clone(), new_rules);
+                    let ruleset = QualRuleset(rules.0.clone(), new_rules, rules.2);
                     ret.push(Ruleset::QualRuleset(ruleset));
                 }
 
@@ -159,6 +291,7 @@ impl<'a> TreeRuleset<'a> {
                     ret.push(Ruleset::QualNestedRuleset(QualNestedRuleset(
                         rules.0.clone(),
                         new_rulesets,
+                        rules.2,
                     )))
                 }
 
@@ -168,21 +301,93 @@ impl<'a> TreeRuleset<'a> {
                 vec![Ruleset::QualNestedRuleset(QualNestedRuleset(
                     ruleset.0.clone(),
                     ruleset.1.iter().flat_map(|x| x.flatten_tree()).collect(),
+                    ruleset.2,
                 ))]
             }
         }
     }
 }
 
-type TreeSelectorRuleset<'a> = SelectorRuleset<'a, TreeRule<'a>>;
+/// The legacy `@nest` prefix of a nested selector which does not start with
+/// `&`, e.g. `@nest .theme-dark & { .. }`.
+pub(super) fn nest_prefix<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    terminated("@nest", multispace1).parse_next(input)
+}
+
+/// Is `qual_rule` a conditional group or other at-rule which may be nested in
+/// a selector ruleset, and is bubbled out of it when flattened, wrapping its
+/// contents in its selector?  For example, `div { @media print { color: red;
+/// } }` becomes `@media print { div { color: red; } }`.
+fn is_bubbled(qual_rule: &QualRule<'_>) -> bool {
+    match qual_rule.at_rule() {
+        AtRule::Media(_) | AtRule::Supports(_) | AtRule::Container(..) | AtRule::Layer(_) => true,
+        AtRule::Unknown(name, _) => name.eq_ignore_ascii_case("scope"),
+        _ => false,
+    }
+}
+
+/// The expected parent of a nested selector with an invalid `&` suffix.
+pub(super) const SUFFIX_CONTEXT: &str = "class, id or type selector before `&` suffix";
+
+/// Find a nested ruleset in `rules` whose selector concatenates a suffix to a
+/// `&` which `parent` can't take, e.g. `&__elem` nested in `.block:hover`, or
+/// `.theme-dark &__elem` nested in `.a .b`.  Deeper rulesets are checked
+/// against their joined parent selectors.
+pub(super) fn invalid_suffix<'a, 'b>(
+    parent: &Selector<'a>,
+    rules: &'b [TreeRule<'a>],
+) -> Option<&'b TreeSelectorRuleset<'a>> {
+    if !has_suffix(rules) {
+        return None;
+    }
+
+    rules.iter().find_map(|rule| match rule {
+        TreeRule::Ruleset(ruleset) => match ruleset.as_ref() {
+            Ruleset::SelectorRuleset(x) => match parent.join(&x.0) {
+                Some(selector) => invalid_suffix(&selector, &x.1),
+                None => Some(x),
+            },
+            Ruleset::QualRuleset(x) => invalid_suffix(parent, &x.1),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Does any ruleset nested in `rules` concatenate a suffix to a `&`?
+fn has_suffix(rules: &[TreeRule<'_>]) -> bool {
+    rules.iter().any(|rule| match rule {
+        TreeRule::Ruleset(ruleset) => match ruleset.as_ref() {
+            Ruleset::SelectorRuleset(x) => x.0.has_suffix() || has_suffix(&x.1),
+            Ruleset::QualRuleset(x) => has_suffix(&x.1),
+            _ => false,
+        },
+        _ => false,
+    })
+}
+
+pub(super) type TreeSelectorRuleset<'a> = SelectorRuleset<'a, TreeRule<'a>>;
 
 impl<'a> ParseCss<'a> for TreeSelectorRuleset<'a> {
-    fn parse<E: ParserError<&'a str>>(input: &'a str) -> IResult<&'a str, Self, E> {
-        let (input, selector) = Selector::parse(input)?;
-        let (input, _) = (comment0, tag("{"), sep0).parse_peek(input)?;
-        let (input, rules) = repeat(0.., unpeek(TreeRule::parse)).parse_peek(input)?;
-        let (input, _) = (comment0, tag("}")).parse_peek(input)?;
-        Ok((input, SelectorRuleset(selector, rules)))
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        let (rest, _) = opt(nest_prefix).parse_peek(input)?;
+        let (rest, selector) = Selector::parse(rest)?;
+        let (rest, _) = (comment0, tag("{"), sep0).parse_peek(rest)?;
+        let (rest, rules): (_, Vec<_>) = repeat(0.., unpeek(TreeRule::parse)).parse_peek(rest)?;
+        let (rest, _) = cut_err((comment0, tag("}")))
+            .context("declaration, ruleset or `}`")
+            .parse_peek(rest)?;
+
+        if let Some(ruleset) = invalid_suffix(&selector, &rules) {
+            let at = &input[input.len() - ruleset.2.start.offset..];
+            let err = E::from_error_kind(&at, ErrorKind::Verify).add_context(&at, SUFFIX_CONTEXT);
+            return Err(ErrMode::Cut(err));
+        }
+
+        Ok((
+            rest,
+            SelectorRuleset(selector, rules, Span::parsed(input, rest)),
+        ))
     }
 }
 
@@ -196,6 +401,8 @@ impl<'a> TransformCss<Vec<TreeRuleset<'a>>> for TreeRuleset<'a> {
             }
             Ruleset::QualRule(_) => (),
             Ruleset::QualRuleset(_) => (),
+            Ruleset::Keyframes(_) => (),
+            Ruleset::Comment(_) => (),
             Ruleset::QualNestedRuleset(ruleset) => {
                 for rule in ruleset.1.iter_mut() {
                     rule.transform_each(f)
@@ -208,44 +415,71 @@ impl<'a> TransformCss<Vec<TreeRuleset<'a>>> for TreeRuleset<'a> {
 impl<'a> TreeSelectorRuleset<'a> {
     /// Flatten a TreeRuleset's SelectorRuleset into a `FlatRuleset`m erging any
     /// nested rulesets which are not allowed in the latter.
-    pub fn flatten_tree(&self) -> Vec<Ruleset<'a, Rule<'a>>> {
-        let mut new_rules: Vec<Rule<'a>> = vec![];
+    pub fn flatten_tree(&self) -> Vec<FlatRuleset<'a>> {
+        let mut new_rules: Vec<FlatRule<'a>> = vec![];
         let mut new_rulesets: Vec<FlatRuleset<'a>> = vec![];
         for rule in self.1.iter() {
             match rule {
-                TreeRule::Rule(rule) => new_rules.push(rule.clone()),
+                TreeRule::Rule(rule) => new_rules.push(FlatRule::Rule(rule.clone())),
+                TreeRule::Comment(x) => new_rules.push(FlatRule::Comment(x.clone())),
                 TreeRule::Ruleset(ruleset) => {
                     if !new_rules.is_empty() {
-                        let ruleset = SelectorRuleset(self.0.clone(), new_rules);
+                        let ruleset = SelectorRuleset(self.0.clone(), new_rules, self.2);
                         new_rulesets.push(Ruleset::SelectorRuleset(ruleset));
                         new_rules = vec![];
                     }
 
-                    let sub_rules = ruleset
-                        .flatten_tree()
-                        .into_iter()
-                        .map(|ruleset| self.join(ruleset));
-                    new_rulesets.extend(sub_rules)
+                    // Nested selectors are joined before flattening their own
+                    // children, so a `&` always refers to the fully resolved
+                    // parent selector.
+                    match ruleset.as_ref() {
+                        Ruleset::SelectorRuleset(inner) => {
+                            if let Some(selector) = self.0.join(&inner.0) {
+                                let ruleset = SelectorRuleset(selector, inner.1.clone(), inner.2);
+                                new_rulesets.extend(ruleset.flatten_tree())
+                            }
+                        }
+                        Ruleset::QualRuleset(inner) if is_bubbled(&inner.0) => {
+                            let ruleset = SelectorRuleset(self.0.clone(), inner.1.clone(), inner.2);
+                            new_rulesets.push(Ruleset::QualNestedRuleset(QualNestedRuleset(
+                                inner.0.clone(),
+                                ruleset.flatten_tree(),
+                                inner.2,
+                            )))
+                        }
+                        ruleset => {
+                            let sub_rules = ruleset
+                                .flatten_tree()
+                                .into_iter()
+                                .filter_map(|ruleset| self.join(ruleset));
+                            new_rulesets.extend(sub_rules)
+                        }
+                    }
                 }
             }
         }
 
         if !new_rules.is_empty() {
-            let ruleset = SelectorRuleset(self.0.clone(), new_rules);
+            let ruleset = SelectorRuleset(self.0.clone(), new_rules, self.2);
             new_rulesets.push(Ruleset::SelectorRuleset(ruleset));
         }
 
         new_rulesets
     }
 
-    /// Join a new `Ruleset` as an extension of self's selector.
-    fn join(&self, rhs: Ruleset<'a, Rule<'a>>) -> Ruleset<'a, Rule<'a>> {
+    /// Join a new `Ruleset` as an extension of self's selector, or `None` if
+    /// its selector can't be joined, see [`Selector::join`].
+    fn join(&self, rhs: FlatRuleset<'a>) -> Option<FlatRuleset<'a>> {
         match rhs {
             Ruleset::SelectorRuleset(inner_ruleset) => {
-                let joined_selector = self.0.join(&inner_ruleset.0);
-                Ruleset::SelectorRuleset(SelectorRuleset(joined_selector, inner_ruleset.1))
+                let joined_selector = self.0.join(&inner_ruleset.0)?;
+                Some(Ruleset::SelectorRuleset(SelectorRuleset(
+                    joined_selector,
+                    inner_ruleset.1,
+                    inner_ruleset.2,
+                )))
             }
-            ruleset => ruleset,
+            ruleset => Some(ruleset),
         }
     }
 }
@@ -0,0 +1,320 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use winnow::{
+    ascii::digit1,
+    combinator::{alt, opt, preceded, repeat, terminated},
+    error::ParserError,
+    token::{any, one_of, take_till0},
+    unpeek, IResult, PResult, Parser,
+};
+
+use super::token::{comment0, parse_ident, parse_name, parse_string_literal};
+use crate::{
+    parser::{ParseCss, ParseCssError},
+    render::RenderCss,
+    transform::TransformCss,
+};
+
+/// A component value of a declaration's value, per
+/// [CSS Syntax](https://www.w3.org/TR/css-syntax-3/#component-value).  Values
+/// borrow their text from the source, so rendering a list of
+/// [`ComponentValue`] with [`RenderCss`] is a cheap way back to a string.
+///
+/// ```
+/// use procss::ast::ComponentValue::{self, *};
+/// assert_eq!(ComponentValue::parse_list("1px solid #fff"), vec![
+///     Dimension("1", "px"),
+///     Whitespace,
+///     Ident("solid"),
+///     Whitespace,
+///     Hash("fff")
+/// ]);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ComponentValue<'a> {
+    /// An identifier, e.g. `solid`.
+    Ident(&'a str),
+
+    /// An `@` keyword (without the `@`), e.g. `@color` variables.
+    AtKeyword(&'a str),
+
+    /// A `#` hash (without the `#`), e.g. `#fff`.
+    Hash(&'a str),
+
+    /// A string, including its quotes.
+    String(&'a str),
+
+    /// A number, e.g. `1.5`.
+    Number(&'a str),
+
+    /// A percentage (without the `%`), e.g. `50%`.
+    Percentage(&'a str),
+
+    /// A number and its unit, e.g. `10px`.
+    Dimension(&'a str, &'a str),
+
+    /// An unquoted `url()` (without the `url(` and `)`).
+    Url(&'a str),
+
+    /// A function and its arguments, e.g. `rgba(0, 0, 0, 0.5)`.
+    Function(&'a str, Vec<ComponentValue<'a>>),
+
+    /// A `()`, `[]` or `{}` block, by its opening character.
+    Block(char, Vec<ComponentValue<'a>>),
+    Comma,
+    Slash,
+    Whitespace,
+
+    /// Any other character.
+    Delim(char),
+}
+
+impl<'a> ComponentValue<'a> {
+    /// Parse `input` into a list of [`ComponentValue`].  This never fails;
+    /// text which is not otherwise a valid token is parsed as
+    /// [`ComponentValue::Delim`].
+    pub fn parse_list(input: &'a str) -> Vec<Self> {
+        let mut values = vec![];
+        let mut rest = input;
+        while let Some(c) = rest.chars().next() {
+            match ComponentValue::parse::<()>(rest) {
+                Ok((next, value)) => {
+                    values.push(value);
+                    rest = next;
+                }
+                Err(_) => {
+                    values.push(ComponentValue::Delim(c));
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+
+        values
+    }
+
+    /// The contents of a string, without its quotes.  Escapes are not
+    /// processed.
+    pub fn string(&self) -> Option<&'a str> {
+        match self {
+            ComponentValue::String(x) => Some(&x[1..x.len() - 1]),
+            _ => None,
+        }
+    }
+
+    /// The path of a `url()`, quoted or unquoted.
+    pub fn url(&self) -> Option<&'a str> {
+        match self {
+            ComponentValue::Url(url) => Some(url.trim()),
+            ComponentValue::Function(name, args) if name.eq_ignore_ascii_case("url") => args
+                .iter()
+                .find(|x| **x != ComponentValue::Whitespace)
+                .and_then(|x| x.string()),
+            _ => None,
+        }
+    }
+
+    /// The numeric value of a number, percentage or dimension.
+    pub fn number(&self) -> Option<f64> {
+        match self {
+            ComponentValue::Number(x)
+            | ComponentValue::Percentage(x)
+            | ComponentValue::Dimension(x, _) => x.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> ParseCss<'a> for ComponentValue<'a> {
+    fn parse<E: ParseCssError<'a>>(input: &'a str) -> IResult<&'a str, Self, E> {
+        alt((
+            whitespace.map(|_| ComponentValue::Whitespace),
+            parse_string_literal().map(ComponentValue::String),
+            numeric,
+            function,
+            parse_ident.map(ComponentValue::Ident),
+            preceded('@', parse_ident).map(ComponentValue::AtKeyword),
+            preceded('#', parse_name).map(ComponentValue::Hash),
+            ','.map(|_| ComponentValue::Comma),
+            '/'.map(|_| ComponentValue::Slash),
+            block,
+            any.verify(|x| !matches!(x, ')' | ']' | '}'))
+                .map(ComponentValue::Delim),
+        ))
+        .parse_peek(input)
+    }
+}
+
+/// Whitespace and comments, which are rendered as a single space.
+fn whitespace<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    comment0
+        .recognize()
+        .verify(|x: &str| !x.is_empty())
+        .parse_next(input)
+}
+
+fn number<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<&'a str, E> {
+    (
+        opt(one_of(['+', '-'])),
+        alt(((digit1, opt(('.', digit1))).void(), ('.', digit1).void())),
+        opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1)),
+    )
+        .recognize()
+        .parse_next(input)
+}
+
+fn numeric<'a, E: ParserError<&'a str>>(input: &mut &'a str) -> PResult<ComponentValue<'a>, E> {
+    let value = number.parse_next(input)?;
+    let unit = opt(alt(("%", parse_ident))).parse_next(input)?;
+    Ok(match unit {
+        Some("%") => ComponentValue::Percentage(value),
+        Some(unit) => ComponentValue::Dimension(value, unit),
+        None => ComponentValue::Number(value),
+    })
+}
+
+fn args<'a, E: ParseCssError<'a>>(input: &mut &'a str) -> PResult<Vec<ComponentValue<'a>>, E> {
+    terminated(
+        repeat(0.., unpeek(ComponentValue::parse)),
+        opt(one_of([')', ']', '}'])),
+    )
+    .parse_next(input)
+}
+
+fn function<'a, E: ParseCssError<'a>>(input: &mut &'a str) -> PResult<ComponentValue<'a>, E> {
+    let name = terminated(parse_ident, '(').parse_next(input)?;
+    let url = (take_till0(|x: char| x == ')' || x == '"' || x == '\''), ')')
+        .recognize()
+        .verify(|_: &str| name.eq_ignore_ascii_case("url"))
+        .map(|x: &'a str| &x[..x.len() - 1]);
+
+    alt((
+        url.map(ComponentValue::Url),
+        args.map(|x| ComponentValue::Function(name, x)),
+    ))
+    .parse_next(input)
+}
+
+fn block<'a, E: ParseCssError<'a>>(input: &mut &'a str) -> PResult<ComponentValue<'a>, E> {
+    let open = one_of(['(', '[', '{']).parse_next(input)?;
+    args.map(|x| ComponentValue::Block(open, x))
+        .parse_next(input)
+}
+
+impl<'a> RenderCss for ComponentValue<'a> {
+    fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComponentValue::Ident(x) | ComponentValue::String(x) | ComponentValue::Number(x) => {
+                write!(f, "{}", x)
+            }
+            ComponentValue::AtKeyword(x) => write!(f, "@{}", x),
+            ComponentValue::Hash(x) => write!(f, "#{}", x),
+            ComponentValue::Percentage(x) => write!(f, "{}%", x),
+            ComponentValue::Dimension(x, unit) => write!(f, "{}{}", x, unit),
+            ComponentValue::Url(x) => write!(f, "url({})", x),
+            ComponentValue::Function(name, args) => {
+                write!(f, "{}(", name)?;
+                args.render(f)?;
+                write!(f, ")")
+            }
+            ComponentValue::Block(open, args) => {
+                let close = match open {
+                    '(' => ')',
+                    '[' => ']',
+                    _ => '}',
+                };
+
+                write!(f, "{}", open)?;
+                args.render(f)?;
+                write!(f, "{}", close)
+            }
+            ComponentValue::Comma => write!(f, ","),
+            ComponentValue::Slash => write!(f, "/"),
+            ComponentValue::Whitespace => write!(f, " "),
+            ComponentValue::Delim(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+impl<'a> TransformCss<ComponentValue<'a>> for ComponentValue<'a> {
+    fn transform_each<F: FnMut(&mut ComponentValue<'a>)>(&mut self, f: &mut F) {
+        f(self);
+        if let ComponentValue::Function(_, args) | ComponentValue::Block(_, args) = self {
+            for arg in args.iter_mut() {
+                arg.transform_each(f);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComponentValue::*, *};
+
+    #[test]
+    fn test_numeric() {
+        assert_eq!(ComponentValue::parse_list("-1.5e3 50% .5em"), vec![
+            Number("-1.5e3"),
+            Whitespace,
+            Percentage("50"),
+            Whitespace,
+            Dimension(".5", "em")
+        ]);
+    }
+
+    #[test]
+    fn test_function() {
+        assert_eq!(ComponentValue::parse_list("rgba(0, 0, 0, 0.5)"), vec![
+            Function("rgba", vec![
+                Number("0"),
+                Comma,
+                Whitespace,
+                Number("0"),
+                Comma,
+                Whitespace,
+                Number("0"),
+                Comma,
+                Whitespace,
+                Number("0.5")
+            ])
+        ]);
+    }
+
+    #[test]
+    fn test_url() {
+        let values = ComponentValue::parse_list("url(./a.png) url(\"./b.png\") url('./c.png')");
+        assert_eq!(values[0], Url("./a.png"));
+        assert_eq!(values[0].url(), Some("./a.png"));
+        assert_eq!(values[2].url(), Some("./b.png"));
+        assert_eq!(values[4].url(), Some("./c.png"));
+    }
+
+    #[test]
+    fn test_separators() {
+        assert_eq!(ComponentValue::parse_list("12px/1.5 @font, serif"), vec![
+            Dimension("12", "px"),
+            Slash,
+            Number("1.5"),
+            Whitespace,
+            AtKeyword("font"),
+            Comma,
+            Whitespace,
+            Ident("serif")
+        ]);
+    }
+
+    #[test]
+    fn test_render() {
+        let input = "calc(100% - var(--x, 2px)) \"a b\" !important";
+        let values = ComponentValue::parse_list(input);
+        assert_eq!(values.as_css_string(), input);
+    }
+}
@@ -35,6 +35,8 @@ pub struct BuildCss<'a> {
     trees: HashMap<&'a Path, ast::Tree<'a>>,
     css: HashMap<&'a Path, ast::Css<'a>>,
     rootdir: PathBuf,
+    preserve_comments: bool,
+    warnings: Vec<String>,
 }
 
 /// The compiled output of a [`BuildCss`] collection, obtained from
@@ -59,16 +61,38 @@ impl<'a> BuildCss<'a> {
             trees: Default::default(),
             css: Default::default(),
             rootdir: rootdir.into(),
+            preserve_comments: false,
+            warnings: Default::default(),
         }
     }
 
-    /// Add a file `path` to this build.
+    /// Render all comments in the compiled output, rather than only
+    /// `/*! ... */` comments, e.g. for debug builds.
+    pub fn preserve_comments(&mut self, preserve: bool) {
+        self.preserve_comments = preserve;
+    }
+
+    /// Add a file `path` to this build, decoding it per its byte-order mark or
+    /// `@charset` (see [`crate::decode`]).
     #[cfg(not(target_arch = "wasm32"))]
     pub fn add_file<P: ?Sized + AsRef<Path>>(&mut self, path: &'a P) {
-        self.paths.push(path.as_ref().into());
         let inpath = self.rootdir.join(path);
-        let txt = fs::read_to_string(&inpath).unwrap();
-        self.contents.insert(path.as_ref(), txt);
+        let bytes = fs::read(&inpath).unwrap();
+        self.add_bytes(path, &bytes);
+    }
+
+    /// Add a file `path` to this build from its undecoded contents, which are
+    /// decoded per their byte-order mark or `@charset` (see
+    /// [`crate::decode`]).  An unsupported `@charset` is reported in the
+    /// `warnings()` of the [`BuildCss::compile`] output.
+    pub fn add_bytes<P: ?Sized + AsRef<Path>>(&mut self, path: &'a P, bytes: &[u8]) {
+        let (txt, warning) = crate::decode(bytes);
+        if let Some(warning) = warning {
+            let warning = format!("{}: {}", path.as_ref().display(), warning);
+            self.warnings.push(warning);
+        }
+
+        self.add_content(path, txt.into_owned());
     }
 
     /// Add a file `path` to this build.
@@ -82,26 +106,84 @@ impl<'a> BuildCss<'a> {
     pub fn compile(&'a mut self) -> anyhow::Result<CompiledCss<'a>> {
         for (path, contents) in &self.contents {
             let tree = ast::Tree::parse(contents);
-            let (_, tree) = tree.map_err(|err| unwrap_parse_error(contents, err))?;
+            let (_, tree) = tree.map_err(|err| unwrap_parse_error(contents, err, Some(path)))?;
             self.trees.insert(path, tree);
         }
 
+        let warnings = self.check_keyframes();
+        self.warnings.extend(warnings);
         let dep_trees = self.trees.clone();
         for (path, tree) in self.trees.iter_mut() {
             transformers::apply_import(&dep_trees)(tree);
             transformers::apply_mixin(tree);
             transformers::apply_var(tree);
-            self.css.insert(path, tree.flatten_tree());
+            let mut css = tree.flatten_tree();
+            transformers::merge_media(&mut css);
+            self.css.insert(path, css);
         }
 
         for (path, css) in self.css.iter_mut() {
             let srcdir = utils::join_paths(&self.rootdir, path);
             transformers::inline_url(&srcdir.to_string_lossy())(css);
             transformers::dedupe(css);
+            transformers::normalize_charset(css);
+            if self.preserve_comments {
+                transformers::preserve_comments(css);
+            }
         }
 
         Ok(CompiledCss(self))
     }
+
+    /// Warn when two files compiled into the same output (via `@import`)
+    /// define an `@keyframes` of the same name, as the latter would silently
+    /// replace the former.
+    fn check_keyframes(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        let mut defs: HashMap<&Path, (Vec<&str>, Vec<&Path>)> = HashMap::new();
+        for (path, tree) in self.trees.iter() {
+            let (names, imports) = defs.entry(path).or_default();
+            for ruleset in tree.iter() {
+                scan_keyframes(ruleset, names, imports);
+            }
+        }
+
+        for root in self.paths.iter() {
+            let mut owners: HashMap<&str, &Path> = HashMap::new();
+            let mut queue = vec![root.as_path()];
+            let mut visited = vec![];
+            while let Some(path) = queue.pop() {
+                if visited.contains(&path) {
+                    continue;
+                }
+
+                visited.push(path);
+                let Some((names, imports)) = defs.get(path) else {
+                    continue;
+                };
+
+                for name in names {
+                    match owners.get(name) {
+                        Some(owner) if *owner != path => warnings.push(format!(
+                            "@keyframes `{}` is defined in both {} and {}, which are compiled \
+                             into {}",
+                            name,
+                            owner.display(),
+                            path.display(),
+                            root.display()
+                        )),
+                        _ => {
+                            owners.insert(name, path);
+                        }
+                    };
+                }
+
+                queue.extend(imports.iter().rev());
+            }
+        }
+
+        warnings
+    }
 }
 
 impl<'a> CompiledCss<'a> {
@@ -131,6 +213,12 @@ impl<'a> CompiledCss<'a> {
         Ok(results)
     }
 
+    /// Warnings found while compiling, e.g. `@keyframes` names which are
+    /// defined by more than one file compiled into the same output.
+    pub fn warnings(&self) -> &[String] {
+        &self.0.warnings
+    }
+
     fn iter_files(&self) -> impl Iterator<Item = anyhow::Result<(String, String, &'_ Path)>> {
         self.0.css.iter().map(|(path, css)| {
             let outpath = PathBuf::from(path);
@@ -147,6 +235,40 @@ impl<'a> CompiledCss<'a> {
     }
 }
 
+/// Collect the `@keyframes` names and `@import` paths of `ruleset`, including
+/// those nested in it.
+fn scan_keyframes<'a, 'b>(
+    ruleset: &'b ast::TreeRuleset<'a>,
+    names: &mut Vec<&'b str>,
+    imports: &mut Vec<&'b Path>,
+) {
+    match ruleset {
+        ast::Ruleset::Keyframes(keyframes) => names.extend(keyframes.name()),
+        ast::Ruleset::QualRule(qual_rule) => {
+            if let ast::AtRule::Import(ast::Import { url, .. }) = qual_rule.at_rule() {
+                let path = url
+                    .string()
+                    .or(url.url().and_then(|x| x.strip_prefix("ref://")));
+                imports.extend(path.map(Path::new));
+            }
+        }
+        ast::Ruleset::SelectorRuleset(ast::SelectorRuleset(_, rules, _))
+        | ast::Ruleset::QualRuleset(ast::QualRuleset(_, rules, _)) => {
+            for rule in rules.iter() {
+                if let ast::TreeRule::Ruleset(ruleset) = rule {
+                    scan_keyframes(ruleset, names, imports);
+                }
+            }
+        }
+        ast::Ruleset::QualNestedRuleset(ast::QualNestedRuleset(_, rulesets, _)) => {
+            for ruleset in rulesets.iter() {
+                scan_keyframes(ruleset, names, imports);
+            }
+        }
+        ast::Ruleset::Comment(_) => (),
+    }
+}
+
 #[cfg(all(test, feature = "iotest"))]
 mod tests {
     use std::{cell::RefCell, path::*, rc::Rc};
@@ -159,11 +281,11 @@ mod tests {
         let infiles = Rc::new(RefCell::new(vec![]));
         let outfiles = Rc::new(RefCell::new(vec![]));
 
-        let ctx = fs::read_to_string_context();
+        let ctx = fs::read_context();
         let infiles2 = infiles.clone();
         ctx.expect().times(1).returning_st(move |x: &Path| {
             infiles2.borrow_mut().push(x.to_string_lossy().to_string());
-            Ok("div{.open{color:green}}".to_owned())
+            Ok(b"div{.open{color:green}}".to_vec())
         });
 
         let ctx = fs::create_dir_all_context();
@@ -190,4 +312,56 @@ mod tests {
         let outfiles = outfiles.borrow().clone();
         assert_eq!(outfiles, vec!["./dist/app/component.css".to_owned()])
     }
+
+    #[test]
+    fn test_keyframes_clash() {
+        let mut build = BuildCss::new("./src".to_owned());
+        build.add_content("a.scss", "@keyframes spin{from{opacity:0}}".to_owned());
+        build.add_content("b.scss", "div{@keyframes spin{to{opacity:1}}}".to_owned());
+        build.add_content(
+            "c.scss",
+            "@import \"a.scss\";@import \"b.scss\";".to_owned(),
+        );
+        let css = build.compile().unwrap();
+        let warning = "@keyframes `spin` is defined in both a.scss and b.scss, which are compiled \
+                       into c.scss";
+        assert_eq!(css.warnings(), [warning]);
+    }
+
+    #[test]
+    fn test_keyframes_shared_import() {
+        let mut build = BuildCss::new("./src".to_owned());
+        build.add_content("a.scss", "@keyframes spin{from{opacity:0}}".to_owned());
+        build.add_content("b.scss", "@import \"a.scss\";".to_owned());
+        build.add_content(
+            "c.scss",
+            "@import \"a.scss\";@import \"b.scss\";".to_owned(),
+        );
+        assert!(build.compile().unwrap().warnings().is_empty());
+    }
+
+    #[test]
+    fn test_charset() {
+        let mut build = BuildCss::new("./src".to_owned());
+        let latin1 = b"@charset \"iso-8859-1\";a::after{content:\"\xe9\"}";
+        build.add_bytes("a.scss", latin1);
+        build.add_bytes("b.scss", b"\xef\xbb\xbf@import \"a.scss\";div{color:red}");
+        build.add_bytes("c.scss", b"div{color:red}");
+        let css = build.compile().unwrap().as_strings().unwrap();
+        assert_eq!(
+            css["b.css"],
+            "@charset \"UTF-8\";a::after{content:\"\u{e9}\";}div{color:red;}"
+        );
+        assert_eq!(css["c.css"], "div{color:red;}");
+    }
+
+    #[test]
+    fn test_charset_unsupported() {
+        let mut build = BuildCss::new("./src".to_owned());
+        build.add_bytes("a.scss", b"@charset \"koi8-r\";a{content:\"\xe9\"}");
+        let css = build.compile().unwrap();
+        assert_eq!(css.warnings(), [
+            "a.scss: Unsupported @charset \"koi8-r\" ignored"
+        ]);
+    }
 }
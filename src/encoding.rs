@@ -0,0 +1,173 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use std::borrow::Cow;
+
+/// The encodings [`decode`] supports.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+}
+
+/// Decode the bytes of a stylesheet to text, per
+/// [CSS Syntax](https://www.w3.org/TR/css-syntax-3/#input-byte-stream).  The
+/// encoding is determined by a byte-order mark, then by an `@charset` rule at
+/// the very start of the stylesheet.  Stylesheets which declare neither are
+/// decoded as UTF-8, or as `windows-1252` (a superset of Latin-1) if they are
+/// not valid UTF-8.  A byte-order mark is not included in the returned text,
+/// which is borrowed from `bytes` when it is already UTF-8.
+///
+/// Stylesheets whose `@charset` names an encoding this crate doesn't support
+/// are decoded as if they declared none, and a warning naming the label is
+/// returned along with the text.
+///
+/// # Example
+///
+/// ```rust
+/// let bytes = b"@charset \"iso-8859-1\";\ndiv::after{content:\"\xe9\"}";
+/// let (text, warning) = procss::decode(bytes);
+/// assert!(text.ends_with("div::after{content:\"é\"}"));
+/// assert!(warning.is_none());
+/// ```
+pub fn decode(bytes: &[u8]) -> (Cow<'_, str>, Option<String>) {
+    let mut warning = None;
+    let (encoding, bytes) = if let Some(rest) = bytes.strip_prefix(b"\xef\xbb\xbf") {
+        (Some(Encoding::Utf8), rest)
+    } else if let Some(rest) = bytes.strip_prefix(b"\xff\xfe") {
+        (Some(Encoding::Utf16Le), rest)
+    } else if let Some(rest) = bytes.strip_prefix(b"\xfe\xff") {
+        (Some(Encoding::Utf16Be), rest)
+    } else {
+        let encoding = charset(bytes).unwrap_or_else(|label| {
+            warning = Some(format!("Unsupported @charset \"{}\" ignored", label));
+            None
+        });
+
+        (encoding, bytes)
+    };
+
+    let text = match encoding {
+        Some(Encoding::Utf8) => String::from_utf8_lossy(bytes),
+        Some(Encoding::Utf16Le) => utf16(bytes, u16::from_le_bytes).into(),
+        Some(Encoding::Utf16Be) => utf16(bytes, u16::from_be_bytes).into(),
+        Some(Encoding::Windows1252) => windows_1252(bytes).into(),
+        None => match std::str::from_utf8(bytes) {
+            Ok(text) => text.into(),
+            Err(_) => windows_1252(bytes).into(),
+        },
+    };
+
+    (text, warning)
+}
+
+/// The encoding declared by an `@charset` rule at the very start of `bytes`,
+/// which must be written exactly as `@charset "<label>";`, or the label if
+/// it is not supported.
+fn charset(bytes: &[u8]) -> Result<Option<Encoding>, String> {
+    let Some(rest) = bytes.strip_prefix(b"@charset \"") else {
+        return Ok(None);
+    };
+
+    let Some(end) = rest.iter().take(64).position(|x| *x == b'"') else {
+        return Ok(None);
+    };
+
+    if rest.get(end + 1) != Some(&b';') {
+        return Ok(None);
+    }
+
+    let label = String::from_utf8_lossy(&rest[..end]).to_ascii_lowercase();
+    match label.trim() {
+        // `@charset` is itself ASCII, so a UTF-16 document can't declare
+        // itself with one and is decoded as UTF-8 instead, per the spec.
+        "utf-8" | "utf8" | "unicode-1-1-utf-8" | "utf-16" | "utf-16le" | "utf-16be" => {
+            Ok(Some(Encoding::Utf8))
+        }
+        "windows-1252" | "cp1252" | "x-cp1252" | "iso-8859-1" | "iso8859-1" | "iso_8859-1"
+        | "iso88591" | "latin1" | "l1" | "cp819" | "ibm819" | "ascii" | "us-ascii" => {
+            Ok(Some(Encoding::Windows1252))
+        }
+        label => Err(label.to_owned()),
+    }
+}
+
+fn utf16(bytes: &[u8], f: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks(2).map(|x| match *x {
+        [a, b] => f([a, b]),
+        _ => 0xfffd,
+    });
+
+    char::decode_utf16(units)
+        .map(|x| x.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// The characters of `windows-1252` bytes `0x80` through `0x9f`, which differ
+/// from Latin-1.  The rest of the encoding maps bytes to the code point of the
+/// same value.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+fn windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|x| match x {
+            0x80..=0x9f => WINDOWS_1252[(x - 0x80) as usize],
+            _ => *x as char,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn test_decode_bom() {
+        assert_eq!(decode(b"\xef\xbb\xbfdiv{}").0, "div{}");
+        assert_eq!(decode(b"\xff\xfed\0i\0v\0").0, "div");
+        assert_eq!(decode(b"\xfe\xff\0d\0i\0v").0, "div");
+    }
+
+    #[test]
+    fn test_decode_charset() {
+        let (text, warning) = decode(b"@charset \"windows-1252\";a{content:\"\x80\xe9\"}");
+        assert_eq!(text, "@charset \"windows-1252\";a{content:\"€é\"}");
+        assert_eq!(warning, None);
+
+        let (text, _) = decode("@charset \"UTF-8\";a{content:\"é\"}".as_bytes());
+        assert_matches!(text, Cow::Borrowed(_));
+    }
+
+    #[test]
+    fn test_decode_charset_unsupported() {
+        let (_, warning) = decode(b"@charset \"KOI8-R\";a{content:\"\xe9\"}");
+        assert_eq!(
+            warning.as_deref(),
+            Some("Unsupported @charset \"koi8-r\" ignored")
+        );
+    }
+
+    #[test]
+    fn test_decode_fallback() {
+        assert_eq!(decode("a{content:\"é\"}".as_bytes()).0, "a{content:\"é\"}");
+        assert_eq!(decode(b"a{content:\"\xe9\"}").0, "a{content:\"é\"}");
+    }
+}
@@ -80,14 +80,17 @@
 
 pub mod ast;
 mod builder;
+mod encoding;
 #[cfg(target_arch = "wasm32")]
 mod js_builder;
+mod owned;
 mod parser;
 mod render;
 mod transform;
 pub mod transformers;
 #[cfg(feature = "iotest")]
 pub mod utils;
+pub mod visit;
 
 #[cfg(not(feature = "iotest"))]
 pub mod utils;
@@ -96,11 +99,14 @@ use self::{
     ast::Tree,
     parser::{unwrap_parse_error, ParseCss},
 };
-pub use self::{builder::BuildCss, render::RenderCss};
+pub use self::{
+    builder::BuildCss, encoding::decode, owned::IntoOwned, parser::ParseError, render::RenderCss,
+};
 
 /// Parse CSS text to a [`Tree`] (where it can be further manipulated),
-/// capturing detailed error reporting for a moderate performance impact (using
-/// [`nom::error::VerboseError`]).
+/// capturing detailed error reporting for a moderate performance impact.
+/// Parse failures are reported as a [`ParseError`], which can be downcast from
+/// the returned [`anyhow::Error`].
 ///
 /// # Example
 ///
@@ -108,10 +114,27 @@ pub use self::{builder::BuildCss, render::RenderCss};
 /// let ast = procss::parse("div { .open { color: red; }}").unwrap();
 /// ```
 pub fn parse(input: &str) -> anyhow::Result<Tree<'_>> {
-    let (_, tree) = Tree::parse(input).map_err(|err| unwrap_parse_error(input, err))?;
+    let (_, tree) = Tree::parse(input).map_err(|err| unwrap_parse_error(input, err, None))?;
     Ok(tree)
 }
 
+/// Parse CSS text to a [`Tree`], skipping invalid declarations and rulesets
+/// the way browsers do rather than failing, and returning a [`ParseError`] for
+/// every problem encountered.  Useful for editors and linters which need to
+/// report all errors in a file at once.
+///
+/// # Example
+///
+/// ```rust
+/// use procss::RenderCss;
+/// let (ast, errors) = procss::parse_recover("div { color red; top: 0 }");
+/// assert_eq!(ast.flatten_tree().as_css_string(), "div{top:0;}");
+/// assert_eq!(errors.len(), 1);
+/// ```
+pub fn parse_recover(input: &str) -> (Tree<'_>, Vec<ParseError>) {
+    Tree::parse_recover(input)
+}
+
 /// Parse CSS text to a [`Tree`], without capturing error details, for maximum
 /// performance without any error details when parsing fails.
 ///
@@ -139,6 +162,102 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_verbose_error_message() {
+        let err = parse("div {\n    color: red").unwrap_err();
+        let err = err.downcast::<ParseError>().unwrap();
+        assert_eq!(
+            err.message,
+            "expected `;` or `}` after declaration `color: red`"
+        );
+        assert_eq!((err.position.line, err.position.column), (2, 15));
+    }
+
+    #[test]
+    fn test_verbose_error_at_rule() {
+        let err = parse("@import \"test.css\"\n}").unwrap_err();
+        let err = err.downcast::<ParseError>().unwrap();
+        assert_eq!(
+            err.message,
+            "expected `;` or `{` after at-rule `@import \"test.css\"`"
+        );
+        assert_eq!((err.position.line, err.position.column), (2, 1));
+    }
+
+    #[test]
+    fn test_verbose_error_top_level() {
+        let err = parse("div {}\n}").unwrap_err();
+        let err = err.downcast::<ParseError>().unwrap();
+        assert_eq!(err.message, "expected selector or at-rule");
+        assert_eq!((err.position.line, err.position.column), (2, 1));
+    }
+
+    #[test]
+    fn test_verbose_error_suffix() {
+        let err = parse(".block:hover {\n    &__elem { color: red; }\n}").unwrap_err();
+        let err = err.downcast::<ParseError>().unwrap();
+        assert_eq!(
+            err.message,
+            "expected class, id or type selector before `&` suffix"
+        );
+        assert_eq!((err.position.line, err.position.column), (2, 5));
+    }
+
+    #[test]
+    fn test_parse_recover() {
+        let (tree, errors) = parse_recover(
+            "div {\n    color red;\n    top: 0;\n}\n\nspan $ { color: blue; }\n\na { b: c }\n}",
+        );
+
+        assert_eq!(tree.flatten_tree().as_css_string(), "div{top:0;}a{b:c;}");
+        let errors = errors
+            .iter()
+            .map(|x| (x.position.line, x.position.column, x.message.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(errors, vec![
+            (2, 11, "expected `:` after property `color`"),
+            (6, 6, "expected `{` after selector `span`"),
+            (9, 1, "expected selector or at-rule"),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_recover_unclosed() {
+        let (tree, errors) = parse_recover("div { color: red; span { top: 0;");
+        assert_eq!(
+            tree.flatten_tree().as_css_string(),
+            "div{color:red;}div span{top:0;}"
+        );
+
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_recover_valid() {
+        for css in [
+            "div{--x:{a:b};&:hover{color:red}}",
+            "@media print{/*! m */div{a:b}}",
+            "@keyframes spin{from{opacity:0}to{opacity:1}}",
+            "a{b:c}@import \"d.css\";.e{&__f{g:h}}",
+        ] {
+            let (tree, errors) = parse_recover(css);
+            assert!(errors.is_empty());
+            assert_eq!(
+                tree.flatten_tree().as_css_string(),
+                parse(css).unwrap().flatten_tree().as_css_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_recover_custom_property_block() {
+        let (tree, errors) = parse_recover("div{--x: {a:b}; y:z}");
+        assert_eq!(tree.flatten_tree().as_css_string(), "div{--x:{a:b};y:z;}");
+
+        assert!(errors.is_empty());
+    }
+
     #[test]
     fn test_parse_unchecked() {
         assert_matches!(
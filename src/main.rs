@@ -19,11 +19,40 @@ mod init {
 
     use procss::*;
 
+    /// Render the file at the first argument as CSS, or its AST as JSON when
+    /// the `--json` flag is given.
     pub fn init() -> anyhow::Result<String> {
-        let args: Vec<String> = env::args().collect();
-        let contents = fs::read_to_string(&args[1]);
-        let css = parse(&contents?)?.flatten_tree().as_css_string();
-        Ok(css)
+        let mut args: Vec<String> = env::args().skip(1).collect();
+        let json = args.iter().any(|x| x == "--json");
+        args.retain(|x| x != "--json");
+        let Some(path) = args.first() else {
+            anyhow::bail!("Usage: procss [--json] <file>");
+        };
+
+        let bytes = fs::read(path)?;
+        let (contents, warning) = decode(&bytes);
+        if let Some(warning) = warning {
+            eprintln!("warning: {}", warning);
+        }
+
+        let tree = parse(&contents)?;
+        if json {
+            return to_json(&tree);
+        }
+
+        let mut css = tree.flatten_tree();
+        transformers::normalize_charset(&mut css);
+        Ok(css.as_css_string())
+    }
+
+    #[cfg(feature = "serde")]
+    fn to_json(tree: &ast::Tree) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(tree)?)
+    }
+
+    #[cfg(not(feature = "serde"))]
+    fn to_json(_tree: &ast::Tree) -> anyhow::Result<String> {
+        anyhow::bail!("`--json` requires procss to be built with the `serde` feature")
     }
 }
 
@@ -0,0 +1,73 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use std::borrow::Cow;
+
+/// Convert an AST node which borrows its text from the source it was parsed
+/// from into one which owns it, so it can outlive (or be sent to another
+/// thread without) the source.
+///
+/// # Example
+///
+/// ```rust
+/// use procss::{ast::Tree, IntoOwned, RenderCss};
+///
+/// fn parse_owned(input: &str) -> Tree<'static> {
+///     procss::parse(input).unwrap().into_owned()
+/// }
+///
+/// let tree = parse_owned(&"div { color: red; }".to_owned());
+/// let css = std::thread::spawn(move || tree.flatten_tree().as_css_string());
+/// assert_eq!(css.join().unwrap(), "div{color:red;}");
+/// ```
+pub trait IntoOwned {
+    type Owned: 'static;
+
+    fn into_owned(self) -> Self::Owned;
+}
+
+impl<'a> IntoOwned for Cow<'a, str> {
+    type Owned = Cow<'static, str>;
+
+    fn into_owned(self) -> Cow<'static, str> {
+        Cow::Owned(Cow::into_owned(self))
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Option<T> {
+    type Owned = Option<T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.map(IntoOwned::into_owned)
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Vec<T> {
+    type Owned = Vec<T::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        self.into_iter().map(IntoOwned::into_owned).collect()
+    }
+}
+
+impl<T: IntoOwned, U: IntoOwned> IntoOwned for (T, U) {
+    type Owned = (T::Owned, U::Owned);
+
+    fn into_owned(self) -> Self::Owned {
+        (self.0.into_owned(), self.1.into_owned())
+    }
+}
+
+impl IntoOwned for () {
+    type Owned = ();
+
+    fn into_owned(self) -> Self::Owned {}
+}
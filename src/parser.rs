@@ -9,12 +9,60 @@
 // │                                                                           │
 // └───────────────────────────────────────────────────────────────────────────┘
 
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
 use anyhow::anyhow;
 use winnow::{
-    error::{ErrMode, ParserError, VerboseError},
+    error::{AddContext, ErrMode, ErrorKind, ParserError},
     IResult,
 };
 
+use crate::ast::{LineIndex, Position};
+
+/// The error type bound of [`ParseCss`] parsers.  Parsers label what they
+/// expected at the points where they commit to a parse, which the
+/// [`CssError`] used by [`crate::parse`] collects into a [`ParseError`].
+pub trait ParseCssError<'a>: ParserError<&'a str> + AddContext<&'a str, &'static str> {}
+
+impl<'a, E> ParseCssError<'a> for E where E: ParserError<&'a str> + AddContext<&'a str, &'static str>
+{}
+
+/// The [`ParseCssError`] used by [`crate::parse`], which records where a
+/// parser failed and the context labels of the parsers it failed within,
+/// innermost first.
+#[derive(Clone, Debug, Default)]
+pub struct CssError<'a> {
+    input: Option<&'a str>,
+    context: Vec<(&'a str, &'static str)>,
+}
+
+impl<'a> ParserError<&'a str> for CssError<'a> {
+    fn from_error_kind(input: &&'a str, _kind: ErrorKind) -> Self {
+        CssError {
+            input: Some(*input),
+            context: vec![],
+        }
+    }
+
+    fn append(self, _input: &&'a str, _kind: ErrorKind) -> Self {
+        self
+    }
+
+    fn or(self, other: Self) -> Self {
+        other
+    }
+}
+
+impl<'a> AddContext<&'a str, &'static str> for CssError<'a> {
+    fn add_context(mut self, input: &&'a str, label: &'static str) -> Self {
+        self.context.push((*input, label));
+        self
+    }
+}
+
 /// A trait for CSS AST types which can be parsed from a String.
 pub trait ParseCss<'a>
 where
@@ -25,14 +73,170 @@ where
     /// fast or debug parser implementations.
     fn parse<E>(input: &'a str) -> IResult<&'a str, Self, E>
     where
-        E: ParserError<&'a str>;
+        E: ParseCssError<'a>;
 }
 
-pub fn unwrap_parse_error(input: &str, err: ErrMode<VerboseError<&str>>) -> anyhow::Error {
-    match err {
-        ErrMode::Backtrack(e) | ErrMode::Cut(e) => {
-            anyhow!("Error parsing, unknown:\n{}", e)
+/// A human readable parse error, with the location the error occurred at and
+/// the line of source it occurred on, which is rendered as a code frame when
+/// formatted via [`std::fmt::Display`].
+///
+/// ```text
+/// error: expected `;` or `}` after declaration `color: red`
+///  --> theme.css:3:19
+///   |
+/// 3 |         color: red
+///   |                   ^
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParseError {
+    pub message: String,
+    pub path: Option<PathBuf>,
+    pub position: Position,
+    line: String,
+}
+
+impl ParseError {
+    /// Create a new [`ParseError`] at `offset` in `input`.
+    pub(crate) fn new(input: &str, offset: usize, message: String, path: Option<&Path>) -> Self {
+        let index = LineIndex::new(input);
+        let position = index.position(offset);
+        let line = input[index.line_start(offset)..]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+
+        ParseError {
+            message,
+            path: path.map(PathBuf::from),
+            position,
+            line,
         }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Position { line, column, .. } = self.position;
+        let gutter = " ".repeat(line.to_string().len());
+        writeln!(f, "error: {}", self.message)?;
+        match &self.path {
+            Some(path) => writeln!(f, "{}--> {}:{}:{}", gutter, path.display(), line, column)?,
+            None => writeln!(f, "{}--> {}:{}", gutter, line, column)?,
+        };
+
+        let marker = self
+            .line
+            .chars()
+            .take(column - 1)
+            .map(|x| if x == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", line, self.line)?;
+        write!(f, "{} | {}^", gutter, marker)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    /// Convert a [`CssError`] into a [`ParseError`], using the error's
+    /// location and the innermost context label as the expected token.  If
+    /// the context started before the error location, the source between them
+    /// is quoted to describe what was parsed up to the error.
+    pub(crate) fn from_css_error(input: &str, err: CssError<'_>, path: Option<&Path>) -> Self {
+        let rest = err.input.unwrap_or(input);
+        let offset = input.len() - rest.len();
+        let context = err.context.first().copied();
+
+        let message = match context {
+            Some((ctx_input, label)) => {
+                let parsed = ctx_input[..ctx_input.len() - rest.len()].trim();
+                let parsed = parsed.lines().next().unwrap_or_default();
+                if parsed.is_empty() {
+                    format!("expected {}", label)
+                } else if parsed.chars().count() > 40 {
+                    let parsed = parsed.chars().take(40).collect::<String>();
+                    format!("expected {} `{}...`", label, parsed)
+                } else {
+                    format!("expected {} `{}`", label, parsed)
+                }
+            }
+            None if rest.is_empty() => "unexpected end of input".to_owned(),
+            None => "unexpected input".to_owned(),
+        };
+
+        ParseError::new(input, offset, message, path)
+    }
+}
+
+/// Convert a parser error into an [`anyhow::Error`] wrapping a [`ParseError`].
+pub fn unwrap_parse_error(
+    input: &str,
+    err: ErrMode<CssError<'_>>,
+    path: Option<&Path>,
+) -> anyhow::Error {
+    match err {
         ErrMode::Incomplete(needed) => anyhow!("Error parsing, unexpected input:\n {:?}", needed),
+        err => into_parse_error(input, err, path).into(),
+    }
+}
+
+/// Convert a parser error into a [`ParseError`], located at the end of
+/// `input` if the parser needed more input.
+pub(crate) fn into_parse_error(
+    input: &str,
+    err: ErrMode<CssError<'_>>,
+    path: Option<&Path>,
+) -> ParseError {
+    match err {
+        ErrMode::Backtrack(e) | ErrMode::Cut(e) => ParseError::from_css_error(input, e, path),
+        ErrMode::Incomplete(_) => ParseError::new(
+            input,
+            input.len(),
+            "unexpected end of input".to_owned(),
+            path,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_frame() {
+        let input = "div {\n    color: red";
+        let err = ParseError::new(input, input.len(), "expected `}`".to_owned(), None);
+        assert_eq!(
+            err.to_string(),
+            "error: expected `}`\n --> 2:15\n  |\n2 |     color: red\n  |               ^"
+        );
+    }
+
+    #[test]
+    fn test_code_frame_multibyte() {
+        let input = "é {\n    ✓: red";
+        let err = ParseError::new(input, input.len(), "expected `}`".to_owned(), None);
+        assert_eq!(
+            err.to_string(),
+            "error: expected `}`\n --> 2:11\n  |\n2 |     ✓: red\n  |           ^"
+        );
+
+        let err = crate::parse("é{color:red").unwrap_err();
+        let err = err.downcast::<ParseError>().unwrap();
+        assert_eq!((err.position.line, err.position.column), (1, 12));
+    }
+
+    #[test]
+    fn test_code_frame_path() {
+        let input = "div {\n    color: red";
+        let path = Path::new("app.css");
+        let err = ParseError::new(input, 4, "expected `}`".to_owned(), Some(path));
+        assert_eq!(
+            err.to_string(),
+            "error: expected `}`\n --> app.css:1:5\n  |\n1 | div {\n  |     ^"
+        );
     }
 }
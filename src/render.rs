@@ -45,7 +45,13 @@ impl<'a> RenderCss for &'a str {
     }
 }
 
-impl<'a, T: RenderCss + Clone> RenderCss for Cow<'a, T> {
+impl RenderCss for str {
+    fn render(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl<'a, T: RenderCss + ToOwned + ?Sized> RenderCss for Cow<'a, T> {
     fn render(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.as_ref().render(f)
     }
@@ -20,25 +20,21 @@ pub fn apply_import<'a, 'b>(assets: &'b HashMap<&Path, Tree<'a>>) -> impl Fn(&mu
     |tree| {
         tree.transform(|ruleset| {
             let mut replace = None;
-            if let Ruleset::QualRule(QualRule(name, val)) = ruleset {
-                if *name == "import" {
-                    if let Some(val) = val {
-                        if val.starts_with('\"') {
-                            replace = assets.get(Path::new(&val[1..val.len() - 1])).cloned();
-                            if replace.is_none() {
-                                panic!("File not found: '{}'", &val[1..val.len() - 1])
-                            }
-                        } else if val.starts_with("url(\"ref://") {
-                            replace = assets.get(Path::new(&val[11..val.len() - 2])).cloned().map(
-                                |mut x| {
-                                    filter_refs(&mut x);
-                                    x
-                                },
-                            );
+            if let Ruleset::QualRule(qual_rule) = ruleset {
+                if let AtRule::Import(Import { url, .. }) = qual_rule.at_rule() {
+                    if let Some(path) = url.string() {
+                        replace = assets.get(Path::new(path)).cloned();
+                        if replace.is_none() {
+                            panic!("File not found: '{}'", path)
+                        }
+                    } else if let Some(path) = url.url().and_then(|x| x.strip_prefix("ref://")) {
+                        replace = assets.get(Path::new(path)).cloned().map(|mut x| {
+                            filter_refs(&mut x);
+                            x
+                        });
 
-                            if replace.is_none() {
-                                panic!("File not found: '{}'", &val[1..val.len() - 1])
-                            }
+                        if replace.is_none() {
+                            panic!("File not found: '{}'", path)
                         }
                     }
                 }
@@ -46,10 +42,14 @@ pub fn apply_import<'a, 'b>(assets: &'b HashMap<&Path, Tree<'a>>) -> impl Fn(&mu
 
             if let Some(replace) = replace {
                 let rules: Vec<TreeRule<'a>> =
-                    replace.iter().cloned().map(TreeRule::Ruleset).collect();
+                    replace.iter().cloned().map(TreeRule::from).collect();
 
-                *ruleset =
-                    Ruleset::SelectorRuleset(SelectorRuleset(Selector::default(), rules.clone()))
+                let span = ruleset.span();
+                *ruleset = Ruleset::SelectorRuleset(SelectorRuleset(
+                    Selector::default(),
+                    rules.clone(),
+                    span,
+                ))
             }
         });
     }
@@ -36,16 +36,17 @@ use crate::ast::*;
 /// assert_eq!(css, "div.open{color:red;}div.open{opacity:0;}");
 /// ```
 pub fn apply_mixin<'a>(tree: &mut Tree<'a>) {
-    let mut mixins: HashMap<&'a str, Vec<TreeRule<'a>>> = HashMap::new();
+    let mut mixins: HashMap<String, Vec<TreeRule<'a>>> = HashMap::new();
     tree.transform(|ruleset| {
-        if let Ruleset::QualRuleset(crate::ast::QualRuleset(QualRule(name, Some(val)), props)) = ruleset {
-            if *name == "mixin"  {
-                mixins.insert(val.trim(), props.clone());
+        if let Ruleset::QualRuleset(crate::ast::QualRuleset(qual_rule, props, _)) = ruleset {
+            if let AtRule::Mixin(name) = qual_rule.at_rule() {
+                mixins.insert(name.to_owned(), props.clone());
             }
         }
 
-        if matches!(ruleset,  Ruleset::QualRuleset(QualRuleset(QualRule(name, _), _)) if *name == "mixin") {
-            *ruleset = Ruleset::SelectorRuleset(SelectorRuleset(Selector::default(), vec![]))
+        if matches!(ruleset,  Ruleset::QualRuleset(QualRuleset(qual_rule, ..)) if matches!(qual_rule.at_rule(), AtRule::Mixin(_))) {
+            let span = ruleset.span();
+            *ruleset = Ruleset::SelectorRuleset(SelectorRuleset(Selector::default(), vec![], span))
         }
     });
 
@@ -54,11 +55,12 @@ pub fn apply_mixin<'a>(tree: &mut Tree<'a>) {
         let mut changed = false;
         tree.transform(|ruleset| {
             for (header, props) in mixins.iter() {
-                if matches!(ruleset, Ruleset::QualRule(QualRule("include", Some(val))) if val == header ) {
+                if matches!(ruleset, Ruleset::QualRule(qual_rule) if qual_rule.at_rule() == AtRule::Include(header)) {
                     changed = true;
                     *ruleset = Ruleset::SelectorRuleset(SelectorRuleset(
                         Selector::default(),
                         props.clone().into_iter().collect(),
+                        ruleset.span(),
                     ))
                 }
             }
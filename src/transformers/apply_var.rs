@@ -13,29 +13,60 @@ use std::collections::HashMap;
 
 use crate::ast::Ruleset::{self};
 use crate::ast::*;
+use crate::render::RenderCss;
+
+fn substitute<'a>(
+    values: Vec<ComponentValue<'a>>,
+    vars: &'a HashMap<String, String>,
+    changed: &mut bool,
+) -> Vec<ComponentValue<'a>> {
+    let mut result = vec![];
+    for value in values {
+        match value {
+            ComponentValue::AtKeyword(name) if vars.contains_key(name) => {
+                *changed = true;
+                result.extend(ComponentValue::parse_list(vars[name].trim()));
+            }
+            ComponentValue::Function(name, args) => {
+                let args = substitute(args, vars, changed);
+                result.push(ComponentValue::Function(name, args));
+            }
+            ComponentValue::Block(open, args) => {
+                let args = substitute(args, vars, changed);
+                result.push(ComponentValue::Block(open, args));
+            }
+            value => result.push(value),
+        }
+    }
+
+    result
+}
 
 pub fn apply_var<'a>(tree: &mut Tree<'a>) {
-    let mut mixins: HashMap<&'a str, &'a str> = HashMap::new();
+    let mut mixins: HashMap<String, String> = HashMap::new();
     tree.transform(|ruleset| {
         let mut is_mixin = false;
-        if let Ruleset::QualRule(QualRule(name, Some(val))) = ruleset {
-            if let Some(val) = val.strip_prefix(':') {
-                mixins.insert(name, val);
+        if let Ruleset::QualRule(qual_rule) = ruleset {
+            if let AtRule::Variable(name, val) = qual_rule.at_rule() {
+                mixins.insert(name.to_owned(), val.to_owned());
                 is_mixin = true;
             }
         }
 
         if is_mixin {
-            *ruleset = Ruleset::QualRuleset(QualRuleset(QualRule("", None), vec![]))
+            *ruleset = Ruleset::QualRuleset(QualRuleset(
+                QualRule("".into(), None, Span::default()),
+                vec![],
+                Span::default(),
+            ))
         }
     });
 
-    let mut mixins = mixins.iter().collect::<Vec<_>>();
-    mixins.sort_by(|(x, _), (a, _)| x.len().cmp(&a.len()));
-    mixins.reverse();
     tree.transform(|rule: &mut Rule| {
-        for (var, val) in mixins.iter() {
-            rule.value = rule.value.replace(&format!("@{}", var), val).into();
+        let mut changed = false;
+        let values = substitute(rule.values(), &mixins, &mut changed);
+        if changed {
+            rule.value = values.as_css_string().into();
         }
     });
 }
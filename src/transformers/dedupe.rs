@@ -19,7 +19,7 @@ pub fn dedupe(css: &mut Css) {
         (Ruleset::SelectorRuleset(x), Ruleset::SelectorRuleset(y)) if x.0 == y.0 => {
             let mut tail = x.1.clone();
             tail.extend(y.1);
-            Ruleset::SelectorRuleset(SelectorRuleset(x.0.clone(), tail))
+            Ruleset::SelectorRuleset(SelectorRuleset(x.0.clone(), tail, x.2))
         }
         x => {
             res.push(x.0);
@@ -21,6 +21,8 @@ pub fn filter_refs(tree: &mut Tree) {
                 Ruleset::QualRule(_) => true,
                 Ruleset::QualRuleset(_) => true,
                 Ruleset::QualNestedRuleset(_) => true,
+                Ruleset::Keyframes(_) => true,
+                Ruleset::Comment(_) => false,
             })
             .collect(),
     )
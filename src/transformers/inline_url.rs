@@ -11,16 +11,10 @@
 
 use std::{borrow::Cow, path::Path};
 
-use winnow::{
-    combinator::{alt, delimited},
-    token::{tag, take_till1},
-    Parser,
-};
-
 #[cfg(feature = "iotest")]
 use crate::utils::IoTestFs;
 use crate::{
-    ast::{Css, Rule},
+    ast::{ComponentValue, Css, Rule},
     utils::fs,
 };
 
@@ -41,10 +35,16 @@ fn read_file_sync(path: &Path) -> Option<Vec<u8>> {
     readFileSync(&*path.to_string_lossy()).ok()
 }
 
-fn parse_url(input: &str) -> winnow::IResult<&str, &str> {
-    let unquoted = delimited(tag("url("), take_till1(')'), tag(")"));
-    let quoted = delimited(tag("url(\""), take_till1('\"'), tag("\")"));
-    alt((quoted, unquoted)).parse_peek(input)
+/// The path of a rule `value` which is exactly one `url()`.
+fn parse_url(value: &str) -> Option<&str> {
+    let mut values = ComponentValue::parse_list(value)
+        .into_iter()
+        .filter(|x| *x != ComponentValue::Whitespace);
+
+    match (values.next(), values.next()) {
+        (Some(value), None) => value.url(),
+        _ => None,
+    }
 }
 
 fn into_data_uri<'a>(path: &Path) -> Option<Cow<'a, str>> {
@@ -65,9 +65,7 @@ fn into_data_uri<'a>(path: &Path) -> Option<Cow<'a, str>> {
 
 fn inline_url_impl<'a>(newpath: &str, flat: &mut Css<'a>) {
     flat.transform::<Rule<'a>>(|rule| {
-        let path = parse_url(&rule.value)
-            .ok()
-            .and_then(|x| x.0.is_empty().then_some(Path::new(newpath).join(x.1)));
+        let path = parse_url(&rule.value).map(|x| Path::new(newpath).join(x));
 
         if let Some(path) = &path {
             if path.starts_with(".") || path.starts_with("/") {
@@ -0,0 +1,147 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use crate::{ast::*, render::RenderCss};
+
+/// Merge `@media` blocks nested in another `@media` into a single query, for
+/// browsers which don't support nested `@media`.  Comma-separated lists merge
+/// as every combination of their queries, and combinations which can never
+/// match (e.g. `screen` and `print`) are dropped.  Rules before and after a
+/// nested `@media` stay in separate blocks of the outer query, so the cascade
+/// order is unchanged.  Nested `@media` with a `not` query are left nested.
+///
+/// # Example
+///
+/// ```
+/// # use procss::{parse, transformers::merge_media, RenderCss};
+/// let css = "
+/// @media screen {
+///     div { color: red; }
+///     @media (min-width: 600px), print {
+///         div { color: blue; }
+///     }
+/// }
+/// ";
+/// let mut css = parse(css).unwrap().flatten_tree();
+/// merge_media(&mut css);
+/// assert_eq!(
+///     css.as_css_string(),
+///     "@media screen{div{color:red;}}@media screen and (min-width:600px){div{color:blue;}}"
+/// );
+/// ```
+pub fn merge_media(css: &mut Css) {
+    let rulesets = std::mem::take(&mut css.0);
+    css.0 = merge_rulesets(rulesets);
+}
+
+fn merge_rulesets<'a>(rulesets: Vec<FlatRuleset<'a>>) -> Vec<FlatRuleset<'a>> {
+    let mut result = vec![];
+    for ruleset in rulesets {
+        match ruleset {
+            Ruleset::QualNestedRuleset(QualNestedRuleset(qual_rule, children, span)) => {
+                let children = merge_rulesets(children);
+                match media_queries(&qual_rule) {
+                    Some(queries) => {
+                        merge_children(&qual_rule, &queries, children, span, &mut result)
+                    }
+                    None => result.push(Ruleset::QualNestedRuleset(QualNestedRuleset(
+                        qual_rule, children, span,
+                    ))),
+                }
+            }
+            x => result.push(x),
+        }
+    }
+
+    result
+}
+
+/// Move the `@media` `children` of the `@media` `qual_rule` up into `result`,
+/// with their queries merged with `queries`.
+fn merge_children<'a>(
+    qual_rule: &QualRule<'a>,
+    queries: &[MediaQuery<'_>],
+    children: Vec<FlatRuleset<'a>>,
+    span: Span,
+    result: &mut Vec<FlatRuleset<'a>>,
+) {
+    let mut group = vec![];
+    for child in children {
+        let merged = match &child {
+            Ruleset::QualNestedRuleset(QualNestedRuleset(inner, ..)) => {
+                media_queries(inner).and_then(|inner| product(queries, &inner))
+            }
+            _ => None,
+        };
+
+        let (
+            Some(merged),
+            Ruleset::QualNestedRuleset(QualNestedRuleset(inner, rulesets, inner_span)),
+        ) = (merged, &child)
+        else {
+            group.push(child);
+            continue;
+        };
+
+        if !group.is_empty() {
+            let group = std::mem::take(&mut group);
+            result.push(Ruleset::QualNestedRuleset(QualNestedRuleset(
+                qual_rule.clone(),
+                group,
+                span,
+            )));
+        }
+
+        if !merged.is_empty() {
+            let qual_rule = QualRule(inner.0.clone(), Some(merged.join(",").into()), inner.2);
+            result.push(Ruleset::QualNestedRuleset(QualNestedRuleset(
+                qual_rule,
+                rulesets.clone(),
+                *inner_span,
+            )));
+        }
+    }
+
+    if !group.is_empty() {
+        result.push(Ruleset::QualNestedRuleset(QualNestedRuleset(
+            qual_rule.clone(),
+            group,
+            span,
+        )));
+    }
+}
+
+fn media_queries<'a>(qual_rule: &'a QualRule<'_>) -> Option<Vec<MediaQuery<'a>>> {
+    match qual_rule.at_rule() {
+        AtRule::Media(queries) => Some(queries),
+        _ => None,
+    }
+}
+
+/// Every combination of `outer` and `inner` which can match, or `None` if
+/// any can't be merged.
+fn product(outer: &[MediaQuery<'_>], inner: &[MediaQuery<'_>]) -> Option<Vec<String>> {
+    if outer.is_empty() || inner.is_empty() {
+        return None;
+    }
+
+    let mut merged = vec![];
+    for x in outer {
+        for y in inner {
+            let query = x.merge(y)?;
+            if !query.is_never() {
+                merged.push(query.as_css_string());
+            }
+        }
+    }
+
+    Some(merged)
+}
@@ -33,6 +33,9 @@ mod dedupe;
 mod filter_refs;
 mod flat_self;
 mod inline_url;
+mod merge_media;
+mod normalize_charset;
+mod preserve_comments;
 
 pub use self::apply_import::apply_import;
 pub use self::apply_mixin::apply_mixin;
@@ -41,3 +44,6 @@ pub use self::dedupe::dedupe;
 pub use self::filter_refs::filter_refs;
 pub(crate) use self::flat_self::flat_self;
 pub use self::inline_url::inline_url;
+pub use self::merge_media::merge_media;
+pub use self::normalize_charset::normalize_charset;
+pub use self::preserve_comments::preserve_comments;
@@ -0,0 +1,30 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use std::borrow::Cow;
+
+use crate::{ast::*, render::RenderCss};
+
+/// Replace the `@charset` rules of `css` (e.g. from inlined `@import`s in
+/// other encodings) with a single `@charset "UTF-8";` as the first rule, as
+/// rendered output is always UTF-8.  When the output is pure ASCII, no
+/// `@charset` is needed and none is rendered.
+pub fn normalize_charset(css: &mut Css) {
+    css.0.retain(|ruleset| {
+        !matches!(ruleset, Ruleset::QualRule(rule) if matches!(rule.at_rule(), AtRule::Charset(_)))
+    });
+
+    if !css.as_css_string().is_ascii() {
+        let prelude = Some(Cow::Borrowed("\"UTF-8\""));
+        let charset = QualRule("charset".into(), prelude, Span::default());
+        css.0.insert(0, Ruleset::QualRule(charset));
+    }
+}
@@ -9,22 +9,10 @@
 // │                                                                           │
 // └───────────────────────────────────────────────────────────────────────────┘
 
-use winnow::{
-    ascii::alphanumeric1,
-    combinator::{alt, repeat},
-    error::ParserError,
-    token::tag,
-    PResult, Parser,
-};
+use crate::ast::*;
 
-pub fn parse_symbol<'a, E>(input: &mut &'a str) -> PResult<&'a str, E>
-where
-    E: ParserError<&'a str>,
-{
-    let mut parser = repeat::<_, _, Vec<_>, _, _>(
-        1..,
-        alt((alphanumeric1, tag("-"), tag("_"), tag("*"), tag("%"))),
-    )
-    .recognize();
-    parser.parse_next(input)
+/// Render all comments between rules and rulesets, rather than only
+/// `/*! ... */` comments, e.g. for debug builds.
+pub fn preserve_comments(css: &mut Css) {
+    css.transform(|comment: &mut Comment| comment.preserve = true);
 }
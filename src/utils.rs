@@ -12,7 +12,7 @@
 use smallvec::SmallVec;
 use winnow::stream::Accumulate;
 
-use crate::render::RenderCss;
+use crate::{owned::IntoOwned, render::RenderCss};
 
 /// A wrapper around [`Vec`] which guarantees at least `N` elements.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -38,6 +38,14 @@ where
     }
 }
 
+impl<T: IntoOwned, const N: usize> IntoOwned for MinVec<T, N> {
+    type Owned = MinVec<T::Owned, N>;
+
+    fn into_owned(self) -> Self::Owned {
+        MinVec(self.0.map(IntoOwned::into_owned), self.1.into_owned())
+    }
+}
+
 impl<T: RenderCss, const N: usize> RenderCss for MinVec<T, N> {
     fn render(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for x in self.0.iter() {
@@ -53,6 +61,31 @@ impl<T: RenderCss, const N: usize> RenderCss for MinVec<T, N> {
     }
 }
 
+/// Serialized as a sequence of all of its elements.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, const N: usize> serde::Serialize for MinVec<T, N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().chain(self.1.iter()))
+    }
+}
+
+/// Deserialized from a sequence, which must have at least `N` elements.
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>, const N: usize> serde::Deserialize<'de> for MinVec<T, N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut head = Vec::<T>::deserialize(deserializer)?;
+        let len = head.len();
+        let tail = head.split_off(N.min(len));
+        match head.try_into() {
+            Ok(head) => Ok(MinVec(head, tail)),
+            Err(_) => Err(serde::de::Error::invalid_length(
+                len,
+                &format!("at least {} elements", N).as_str(),
+            )),
+        }
+    }
+}
+
 /// Givens a root path `outdir` and a relative path `path`, remove the extension
 /// to the latter and join with the former.  If the latter path is not relative,
 /// return the former.  Useful for moving directory trees while retaining their
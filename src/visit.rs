@@ -0,0 +1,739 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+//! Read-only and mutable walks over every node of a [`Tree`] or [`Css`],
+//! with access to the ancestors of the node being visited.
+//!
+//! [`Visit`] and [`VisitMut`] have a method for each node type, which by
+//! default calls the `walk_*` function of the same name to visit the node's
+//! children.  Override a method to act on a node; call the `walk_*` function
+//! from it to continue into its children, code before and after that call
+//! runs on enter and on exit respectively.  Not calling it skips the node's
+//! children, and returning [`ControlFlow::Break`] from any method stops the
+//! walk altogether.
+//!
+//! Each method also gets the [`Context`] of the node, which lists the
+//! rulesets, at-rules, keyframes and pseudo-classes it is nested in.
+//!
+//! # Example
+//!
+//! ```
+//! use std::ops::ControlFlow;
+//!
+//! use procss::{
+//!     ast::Rule,
+//!     visit::{Context, Visit},
+//!     RenderCss,
+//! };
+//!
+//! /// Collect the selectors of `color` declarations within `@media print`.
+//! #[derive(Default)]
+//! struct PrintColors(Vec<String>);
+//!
+//! impl<'ast> Visit<'ast> for PrintColors {
+//!     fn visit_rule(
+//!         &mut self,
+//!         rule: &'ast Rule<'ast>,
+//!         cx: &mut Context<'ast>,
+//!     ) -> ControlFlow<()> {
+//!         let media = cx.at_rule("media").and_then(|x| x.1.as_deref());
+//!         let print = media.map(str::trim) == Some("print");
+//!         if print && rule.property == "color" {
+//!             let selector = cx.selector().map(|x| x.as_css_string());
+//!             self.0.push(selector.unwrap_or_default());
+//!         }
+//!
+//!         ControlFlow::Continue(())
+//!     }
+//! }
+//!
+//! let tree = procss::parse("@media print { div { color: red; span { color: blue } } }").unwrap();
+//! let mut visitor = PrintColors::default();
+//! tree.visit(&mut visitor);
+//! assert_eq!(visitor.0, vec!["div", "span"]);
+//! ```
+
+use std::{borrow::Cow, ops::ControlFlow};
+
+use crate::ast::{
+    Comment, Css, FlatRule, Keyframe, KeyframeSelector, KeyframesRuleset, Nth, Pseudo, PseudoArg,
+    QualNestedRuleset, QualRule, QualRuleset, Qualifier, Rule, Ruleset, Selector, SelectorAttr,
+    SelectorPath, SelectorRuleset, SelectorTerm, Tree, TreeRule,
+};
+
+/// A node which encloses the node being visited.  The nodes are borrowed by a
+/// [`Visit`] walk, and cloned (as of when they were entered) by a
+/// [`VisitMut`] walk.
+#[derive(Clone, Debug)]
+pub enum Ancestor<'a> {
+    /// The selector of a ruleset, e.g. `div` for the rules of `div { ... }`.
+    /// Nested rulesets are not joined with their parents', so each selector
+    /// is as written.
+    Selector(Cow<'a, Selector<'a>>),
+
+    /// The prelude of an at-rule block, e.g. `@media print` or
+    /// `@keyframes spin`.
+    AtRule(Cow<'a, QualRule<'a>>),
+
+    /// The selectors of a frame of an `@keyframes`, e.g. `50%`.
+    Keyframe(Cow<'a, [KeyframeSelector<'a>]>),
+
+    /// A pseudo-class with a selector argument, e.g. `:not(.open)` for the
+    /// selector `.open`.
+    Pseudo(Cow<'a, Pseudo<'a>>),
+}
+
+/// The ancestors of the node being visited.
+#[derive(Clone, Debug, Default)]
+pub struct Context<'a> {
+    ancestors: Vec<Ancestor<'a>>,
+}
+
+impl<'a> Context<'a> {
+    /// The ancestors of the node being visited, outermost first.
+    pub fn ancestors(&self) -> &[Ancestor<'a>] {
+        &self.ancestors
+    }
+
+    /// The selector of the innermost ruleset enclosing the node being
+    /// visited.
+    pub fn selector(&self) -> Option<&Selector<'a>> {
+        self.ancestors.iter().rev().find_map(|x| match x {
+            Ancestor::Selector(selector) => Some(selector.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// The innermost at-rule block named `name` (e.g. `media`) enclosing the
+    /// node being visited.
+    pub fn at_rule(&self, name: &str) -> Option<&QualRule<'a>> {
+        self.ancestors.iter().rev().find_map(|x| match x {
+            Ancestor::AtRule(rule) if rule.0.eq_ignore_ascii_case(name) => Some(rule.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Run `f` with `ancestor` pushed onto the ancestor chain.
+    fn enter<T>(&mut self, ancestor: Ancestor<'a>, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.ancestors.push(ancestor);
+        let result = f(self);
+        self.ancestors.pop();
+        result
+    }
+}
+
+/// A node which contains declarations, i.e. [`TreeRule`] and [`FlatRule`],
+/// the contents of the blocks of [`Tree`] and [`Css`] respectively.
+pub trait Walk<'a> {
+    /// Visit this node with the matching [`Visit`] method.
+    fn walk<V: Visit<'a> + ?Sized>(&'a self, v: &mut V, cx: &mut Context<'a>) -> ControlFlow<()>;
+
+    /// Visit this node with the matching [`VisitMut`] method.
+    fn walk_mut<V: VisitMut<'a> + ?Sized>(
+        &mut self,
+        v: &mut V,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()>;
+}
+
+impl<'a> Walk<'a> for TreeRule<'a> {
+    fn walk<V: Visit<'a> + ?Sized>(&'a self, v: &mut V, cx: &mut Context<'a>) -> ControlFlow<()> {
+        match self {
+            TreeRule::Rule(rule) => v.visit_rule(rule, cx),
+            TreeRule::Ruleset(ruleset) => v.visit_ruleset(ruleset, cx),
+            TreeRule::Comment(comment) => v.visit_comment(comment, cx),
+        }
+    }
+
+    fn walk_mut<V: VisitMut<'a> + ?Sized>(
+        &mut self,
+        v: &mut V,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        match self {
+            TreeRule::Rule(rule) => v.visit_rule_mut(rule, cx),
+            TreeRule::Ruleset(ruleset) => v.visit_ruleset_mut(ruleset, cx),
+            TreeRule::Comment(comment) => v.visit_comment_mut(comment, cx),
+        }
+    }
+}
+
+impl<'a> Walk<'a> for FlatRule<'a> {
+    fn walk<V: Visit<'a> + ?Sized>(&'a self, v: &mut V, cx: &mut Context<'a>) -> ControlFlow<()> {
+        match self {
+            FlatRule::Rule(rule) => v.visit_rule(rule, cx),
+            FlatRule::Comment(comment) => v.visit_comment(comment, cx),
+        }
+    }
+
+    fn walk_mut<V: VisitMut<'a> + ?Sized>(
+        &mut self,
+        v: &mut V,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        match self {
+            FlatRule::Rule(rule) => v.visit_rule_mut(rule, cx),
+            FlatRule::Comment(comment) => v.visit_comment_mut(comment, cx),
+        }
+    }
+}
+
+/// A read-only walk over an AST, see the [module docs](self).
+pub trait Visit<'ast> {
+    fn visit_tree(&mut self, tree: &'ast Tree<'ast>, cx: &mut Context<'ast>) -> ControlFlow<()> {
+        walk_tree(self, tree, cx)
+    }
+
+    fn visit_css(&mut self, css: &'ast Css<'ast>, cx: &mut Context<'ast>) -> ControlFlow<()> {
+        walk_css(self, css, cx)
+    }
+
+    fn visit_ruleset<T: Walk<'ast>>(
+        &mut self,
+        ruleset: &'ast Ruleset<'ast, T>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        walk_ruleset(self, ruleset, cx)
+    }
+
+    fn visit_selector_ruleset<T: Walk<'ast>>(
+        &mut self,
+        ruleset: &'ast SelectorRuleset<'ast, T>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        walk_selector_ruleset(self, ruleset, cx)
+    }
+
+    /// An at-rule statement such as `@import "x.css"`, or the prelude of an
+    /// at-rule block such as `@media print`.
+    fn visit_qual_rule(
+        &mut self,
+        _rule: &'ast QualRule<'ast>,
+        _cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_qual_ruleset<T: Walk<'ast>>(
+        &mut self,
+        ruleset: &'ast QualRuleset<'ast, T>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        walk_qual_ruleset(self, ruleset, cx)
+    }
+
+    fn visit_qual_nested_ruleset<T: Walk<'ast>>(
+        &mut self,
+        ruleset: &'ast QualNestedRuleset<'ast, T>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        walk_qual_nested_ruleset(self, ruleset, cx)
+    }
+
+    fn visit_keyframes(
+        &mut self,
+        keyframes: &'ast KeyframesRuleset<'ast>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        walk_keyframes(self, keyframes, cx)
+    }
+
+    fn visit_keyframe(
+        &mut self,
+        keyframe: &'ast Keyframe<'ast>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        walk_keyframe(self, keyframe, cx)
+    }
+
+    fn visit_comment(
+        &mut self,
+        _comment: &'ast Comment<'ast>,
+        _cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_rule(&mut self, _rule: &'ast Rule<'ast>, _cx: &mut Context<'ast>) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_selector(
+        &mut self,
+        selector: &'ast Selector<'ast>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        walk_selector(self, selector, cx)
+    }
+
+    fn visit_selector_path(
+        &mut self,
+        path: &'ast SelectorPath<'ast>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        walk_selector_path(self, path, cx)
+    }
+
+    /// A compound selector, whose `tag` is `()` when it is the leading `&`
+    /// of a [`SelectorPath::PartialCons`].
+    fn visit_selector_term<T>(
+        &mut self,
+        term: &'ast SelectorTerm<'ast, T>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        walk_selector_term(self, term, cx)
+    }
+
+    fn visit_selector_attr(
+        &mut self,
+        _attr: &'ast SelectorAttr<'ast>,
+        _cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_pseudo(
+        &mut self,
+        pseudo: &'ast Pseudo<'ast>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        walk_pseudo(self, pseudo, cx)
+    }
+}
+
+pub fn walk_tree<'ast, V: Visit<'ast> + ?Sized>(
+    v: &mut V,
+    tree: &'ast Tree<'ast>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    tree.0.iter().try_for_each(|x| v.visit_ruleset(x, cx))
+}
+
+pub fn walk_css<'ast, V: Visit<'ast> + ?Sized>(
+    v: &mut V,
+    css: &'ast Css<'ast>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    css.0.iter().try_for_each(|x| v.visit_ruleset(x, cx))
+}
+
+pub fn walk_ruleset<'ast, V: Visit<'ast> + ?Sized, T: Walk<'ast>>(
+    v: &mut V,
+    ruleset: &'ast Ruleset<'ast, T>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    match ruleset {
+        Ruleset::SelectorRuleset(x) => v.visit_selector_ruleset(x, cx),
+        Ruleset::QualRule(x) => v.visit_qual_rule(x, cx),
+        Ruleset::QualRuleset(x) => v.visit_qual_ruleset(x, cx),
+        Ruleset::QualNestedRuleset(x) => v.visit_qual_nested_ruleset(x, cx),
+        Ruleset::Keyframes(x) => v.visit_keyframes(x, cx),
+        Ruleset::Comment(x) => v.visit_comment(x, cx),
+    }
+}
+
+pub fn walk_selector_ruleset<'ast, V: Visit<'ast> + ?Sized, T: Walk<'ast>>(
+    v: &mut V,
+    ruleset: &'ast SelectorRuleset<'ast, T>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    v.visit_selector(&ruleset.0, cx)?;
+    cx.enter(Ancestor::Selector(Cow::Borrowed(&ruleset.0)), |cx| {
+        ruleset.1.iter().try_for_each(|x| x.walk(v, cx))
+    })
+}
+
+pub fn walk_qual_ruleset<'ast, V: Visit<'ast> + ?Sized, T: Walk<'ast>>(
+    v: &mut V,
+    ruleset: &'ast QualRuleset<'ast, T>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    v.visit_qual_rule(&ruleset.0, cx)?;
+    cx.enter(Ancestor::AtRule(Cow::Borrowed(&ruleset.0)), |cx| {
+        ruleset.1.iter().try_for_each(|x| x.walk(v, cx))
+    })
+}
+
+pub fn walk_qual_nested_ruleset<'ast, V: Visit<'ast> + ?Sized, T: Walk<'ast>>(
+    v: &mut V,
+    ruleset: &'ast QualNestedRuleset<'ast, T>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    v.visit_qual_rule(&ruleset.0, cx)?;
+    cx.enter(Ancestor::AtRule(Cow::Borrowed(&ruleset.0)), |cx| {
+        ruleset.1.iter().try_for_each(|x| v.visit_ruleset(x, cx))
+    })
+}
+
+pub fn walk_keyframes<'ast, V: Visit<'ast> + ?Sized>(
+    v: &mut V,
+    keyframes: &'ast KeyframesRuleset<'ast>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    v.visit_qual_rule(&keyframes.0, cx)?;
+    cx.enter(Ancestor::AtRule(Cow::Borrowed(&keyframes.0)), |cx| {
+        keyframes.1.iter().try_for_each(|x| v.visit_keyframe(x, cx))
+    })
+}
+
+pub fn walk_keyframe<'ast, V: Visit<'ast> + ?Sized>(
+    v: &mut V,
+    keyframe: &'ast Keyframe<'ast>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    cx.enter(Ancestor::Keyframe(Cow::Borrowed(&keyframe.0)), |cx| {
+        keyframe.1.iter().try_for_each(|x| x.walk(v, cx))
+    })
+}
+
+pub fn walk_selector<'ast, V: Visit<'ast> + ?Sized>(
+    v: &mut V,
+    selector: &'ast Selector<'ast>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    selector
+        .iter()
+        .try_for_each(|x| v.visit_selector_path(x, cx))
+}
+
+pub fn walk_selector_path<'ast, V: Visit<'ast> + ?Sized>(
+    v: &mut V,
+    path: &'ast SelectorPath<'ast>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    let tail = match path {
+        SelectorPath::Cons(head, tail) => {
+            v.visit_selector_term(head, cx)?;
+            tail
+        }
+        SelectorPath::PartialCons(head, tail) => {
+            v.visit_selector_term(head, cx)?;
+            tail
+        }
+    };
+
+    tail.iter()
+        .try_for_each(|(_, term)| v.visit_selector_term(term, cx))
+}
+
+pub fn walk_selector_term<'ast, V: Visit<'ast> + ?Sized, T>(
+    v: &mut V,
+    term: &'ast SelectorTerm<'ast, T>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    term.qualifiers.iter().try_for_each(|x| match x {
+        Qualifier::Attribute(x) => v.visit_selector_attr(x, cx),
+        Qualifier::Pseudo(x) => v.visit_pseudo(x, cx),
+        Qualifier::Id(_) | Qualifier::Class(_) => ControlFlow::Continue(()),
+    })
+}
+
+pub fn walk_pseudo<'ast, V: Visit<'ast> + ?Sized>(
+    v: &mut V,
+    pseudo: &'ast Pseudo<'ast>,
+    cx: &mut Context<'ast>,
+) -> ControlFlow<()> {
+    let ancestor = Ancestor::Pseudo(Cow::Borrowed(pseudo));
+    match &pseudo.value {
+        Some(PseudoArg::Selector(selector))
+        | Some(PseudoArg::Nth(Nth {
+            of: Some(selector), ..
+        })) => cx.enter(ancestor, |cx| v.visit_selector(selector, cx)),
+        Some(PseudoArg::Relative(paths)) => cx.enter(ancestor, |cx| {
+            paths
+                .iter()
+                .try_for_each(|(_, path)| v.visit_selector_path(path, cx))
+        }),
+        _ => ControlFlow::Continue(()),
+    }
+}
+
+/// A mutable walk over an AST, see the [module docs](self).  Ancestors in the
+/// [`Context`] are cloned when they are entered, so they do not reflect
+/// changes made to them since.
+pub trait VisitMut<'a> {
+    fn visit_tree_mut(&mut self, tree: &mut Tree<'a>, cx: &mut Context<'a>) -> ControlFlow<()> {
+        walk_tree_mut(self, tree, cx)
+    }
+
+    fn visit_css_mut(&mut self, css: &mut Css<'a>, cx: &mut Context<'a>) -> ControlFlow<()> {
+        walk_css_mut(self, css, cx)
+    }
+
+    fn visit_ruleset_mut<T: Walk<'a>>(
+        &mut self,
+        ruleset: &mut Ruleset<'a, T>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        walk_ruleset_mut(self, ruleset, cx)
+    }
+
+    fn visit_selector_ruleset_mut<T: Walk<'a>>(
+        &mut self,
+        ruleset: &mut SelectorRuleset<'a, T>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        walk_selector_ruleset_mut(self, ruleset, cx)
+    }
+
+    /// An at-rule statement such as `@import "x.css"`, or the prelude of an
+    /// at-rule block such as `@media print`.
+    fn visit_qual_rule_mut(
+        &mut self,
+        _rule: &mut QualRule<'a>,
+        _cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_qual_ruleset_mut<T: Walk<'a>>(
+        &mut self,
+        ruleset: &mut QualRuleset<'a, T>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        walk_qual_ruleset_mut(self, ruleset, cx)
+    }
+
+    fn visit_qual_nested_ruleset_mut<T: Walk<'a>>(
+        &mut self,
+        ruleset: &mut QualNestedRuleset<'a, T>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        walk_qual_nested_ruleset_mut(self, ruleset, cx)
+    }
+
+    fn visit_keyframes_mut(
+        &mut self,
+        keyframes: &mut KeyframesRuleset<'a>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        walk_keyframes_mut(self, keyframes, cx)
+    }
+
+    fn visit_keyframe_mut(
+        &mut self,
+        keyframe: &mut Keyframe<'a>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        walk_keyframe_mut(self, keyframe, cx)
+    }
+
+    fn visit_comment_mut(
+        &mut self,
+        _comment: &mut Comment<'a>,
+        _cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_rule_mut(&mut self, _rule: &mut Rule<'a>, _cx: &mut Context<'a>) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_selector_mut(
+        &mut self,
+        selector: &mut Selector<'a>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        walk_selector_mut(self, selector, cx)
+    }
+
+    fn visit_selector_path_mut(
+        &mut self,
+        path: &mut SelectorPath<'a>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        walk_selector_path_mut(self, path, cx)
+    }
+
+    /// A compound selector, whose `tag` is `()` when it is the leading `&`
+    /// of a [`SelectorPath::PartialCons`].
+    fn visit_selector_term_mut<T>(
+        &mut self,
+        term: &mut SelectorTerm<'a, T>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        walk_selector_term_mut(self, term, cx)
+    }
+
+    fn visit_selector_attr_mut(
+        &mut self,
+        _attr: &mut SelectorAttr<'a>,
+        _cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_pseudo_mut(
+        &mut self,
+        pseudo: &mut Pseudo<'a>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        walk_pseudo_mut(self, pseudo, cx)
+    }
+}
+
+pub fn walk_tree_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    tree: &mut Tree<'a>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    tree.0
+        .iter_mut()
+        .try_for_each(|x| v.visit_ruleset_mut(x, cx))
+}
+
+pub fn walk_css_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    css: &mut Css<'a>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    css.0
+        .iter_mut()
+        .try_for_each(|x| v.visit_ruleset_mut(x, cx))
+}
+
+pub fn walk_ruleset_mut<'a, V: VisitMut<'a> + ?Sized, T: Walk<'a>>(
+    v: &mut V,
+    ruleset: &mut Ruleset<'a, T>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    match ruleset {
+        Ruleset::SelectorRuleset(x) => v.visit_selector_ruleset_mut(x, cx),
+        Ruleset::QualRule(x) => v.visit_qual_rule_mut(x, cx),
+        Ruleset::QualRuleset(x) => v.visit_qual_ruleset_mut(x, cx),
+        Ruleset::QualNestedRuleset(x) => v.visit_qual_nested_ruleset_mut(x, cx),
+        Ruleset::Keyframes(x) => v.visit_keyframes_mut(x, cx),
+        Ruleset::Comment(x) => v.visit_comment_mut(x, cx),
+    }
+}
+
+pub fn walk_selector_ruleset_mut<'a, V: VisitMut<'a> + ?Sized, T: Walk<'a>>(
+    v: &mut V,
+    ruleset: &mut SelectorRuleset<'a, T>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    v.visit_selector_mut(&mut ruleset.0, cx)?;
+    cx.enter(Ancestor::Selector(Cow::Owned(ruleset.0.clone())), |cx| {
+        ruleset.1.iter_mut().try_for_each(|x| x.walk_mut(v, cx))
+    })
+}
+
+pub fn walk_qual_ruleset_mut<'a, V: VisitMut<'a> + ?Sized, T: Walk<'a>>(
+    v: &mut V,
+    ruleset: &mut QualRuleset<'a, T>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    v.visit_qual_rule_mut(&mut ruleset.0, cx)?;
+    cx.enter(Ancestor::AtRule(Cow::Owned(ruleset.0.clone())), |cx| {
+        ruleset.1.iter_mut().try_for_each(|x| x.walk_mut(v, cx))
+    })
+}
+
+pub fn walk_qual_nested_ruleset_mut<'a, V: VisitMut<'a> + ?Sized, T: Walk<'a>>(
+    v: &mut V,
+    ruleset: &mut QualNestedRuleset<'a, T>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    v.visit_qual_rule_mut(&mut ruleset.0, cx)?;
+    cx.enter(Ancestor::AtRule(Cow::Owned(ruleset.0.clone())), |cx| {
+        ruleset
+            .1
+            .iter_mut()
+            .try_for_each(|x| v.visit_ruleset_mut(x, cx))
+    })
+}
+
+pub fn walk_keyframes_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    keyframes: &mut KeyframesRuleset<'a>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    v.visit_qual_rule_mut(&mut keyframes.0, cx)?;
+    cx.enter(Ancestor::AtRule(Cow::Owned(keyframes.0.clone())), |cx| {
+        keyframes
+            .1
+            .iter_mut()
+            .try_for_each(|x| v.visit_keyframe_mut(x, cx))
+    })
+}
+
+pub fn walk_keyframe_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    keyframe: &mut Keyframe<'a>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    cx.enter(Ancestor::Keyframe(Cow::Owned(keyframe.0.clone())), |cx| {
+        keyframe.1.iter_mut().try_for_each(|x| x.walk_mut(v, cx))
+    })
+}
+
+pub fn walk_selector_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    selector: &mut Selector<'a>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    selector
+        .iter_mut()
+        .try_for_each(|x| v.visit_selector_path_mut(x, cx))
+}
+
+pub fn walk_selector_path_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    path: &mut SelectorPath<'a>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    let tail = match path {
+        SelectorPath::Cons(head, tail) => {
+            v.visit_selector_term_mut(head, cx)?;
+            tail
+        }
+        SelectorPath::PartialCons(head, tail) => {
+            v.visit_selector_term_mut(head, cx)?;
+            tail
+        }
+    };
+
+    tail.iter_mut()
+        .try_for_each(|(_, term)| v.visit_selector_term_mut(term, cx))
+}
+
+pub fn walk_selector_term_mut<'a, V: VisitMut<'a> + ?Sized, T>(
+    v: &mut V,
+    term: &mut SelectorTerm<'a, T>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    term.qualifiers.iter_mut().try_for_each(|x| match x {
+        Qualifier::Attribute(x) => v.visit_selector_attr_mut(x, cx),
+        Qualifier::Pseudo(x) => v.visit_pseudo_mut(x, cx),
+        Qualifier::Id(_) | Qualifier::Class(_) => ControlFlow::Continue(()),
+    })
+}
+
+pub fn walk_pseudo_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    pseudo: &mut Pseudo<'a>,
+    cx: &mut Context<'a>,
+) -> ControlFlow<()> {
+    let ancestor = Ancestor::Pseudo(Cow::Owned(pseudo.clone()));
+    match &mut pseudo.value {
+        Some(PseudoArg::Selector(selector))
+        | Some(PseudoArg::Nth(Nth {
+            of: Some(selector), ..
+        })) => cx.enter(ancestor, |cx| v.visit_selector_mut(selector, cx)),
+        Some(PseudoArg::Relative(paths)) => cx.enter(ancestor, |cx| {
+            paths
+                .iter_mut()
+                .try_for_each(|(_, path)| v.visit_selector_path_mut(path, cx))
+        }),
+        _ => ControlFlow::Continue(()),
+    }
+}
@@ -69,3 +69,28 @@ fn test_import_ref() {
         Ok("div.open{color:#00FF00;}")
     )
 }
+
+#[test]
+fn test_apply_import_single_quotes() {
+    let mut trees = HashMap::default();
+    trees.insert(
+        Path::new("test"),
+        parse("div.closed{color: ref}@green: #00FF00;").unwrap(),
+    );
+
+    let mut result = parse(
+        "
+            @import 'test';
+            @import url('ref://test');
+            div.open {
+                color: @green;
+            }
+        ",
+    )
+    .unwrap();
+    apply_import(&trees)(&mut result);
+    apply_var(&mut result);
+
+    let result = result.flatten_tree().as_css_string();
+    assert_matches!(&*result, "div.closed{color:ref;}div.open{color:#00FF00;}")
+}
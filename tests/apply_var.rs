@@ -58,3 +58,44 @@ fn test_var_overlapping_name() {
         Ok("div.open{color:#0000FF;}")
     )
 }
+
+#[test]
+fn test_var_in_function() {
+    assert_matches!(
+        parse(
+            "
+            @size: 10px;
+            div.open {
+                transform: translate(@size, 0);
+                content: \"@size\";
+            }
+        "
+        )
+        .map(|mut x| {
+            apply_var(&mut x);
+            x.flatten_tree().as_css_string()
+        })
+        .as_deref(),
+        Ok("div.open{transform:translate(10px,0);content:\"@size\";}")
+    )
+}
+
+#[test]
+fn test_var_important() {
+    assert_matches!(
+        parse(
+            "
+            @blue: #CCCCFF;
+            div.open {
+                color: @blue !important;
+            }
+        "
+        )
+        .map(|mut x| {
+            apply_var(&mut x);
+            x.flatten_tree().as_css_string()
+        })
+        .as_deref(),
+        Ok("div.open{color:#CCCCFF!important;}")
+    )
+}
@@ -14,7 +14,10 @@
 #[cfg(test)]
 use std::assert_matches::assert_matches;
 
-use procss::{parse, RenderCss};
+use procss::{
+    ast::{AtRule, PageSelector, Ruleset},
+    parse, RenderCss,
+};
 
 #[test]
 fn test_simple() {
@@ -230,6 +233,65 @@ fn test_keyframes() {
     )
 }
 
+#[test]
+fn test_keyframes_selectors() {
+    assert_matches!(
+        parse(
+            "
+            @keyframes fade {
+                FROM, 12.5% {
+                    opacity: 0;
+                }
+
+                to {
+                    opacity: 1;
+                }
+            }
+        "
+        )
+        .map(|x| x.flatten_tree().as_css_string())
+        .as_deref(),
+        Ok("@keyframes fade{from,12.5%{opacity:0;}to{opacity:1;}}")
+    )
+}
+
+#[test]
+fn test_keyframes_nested() {
+    assert_matches!(
+        parse(
+            "
+            div {
+                color: red;
+                @keyframes spin {
+                    from {
+                        opacity: 0;
+                    }
+                }
+
+                @media print {
+                    @keyframes spin {
+                        to {
+                            opacity: 1;
+                        }
+                    }
+                }
+            }
+        "
+        )
+        .map(|x| x.flatten_tree().as_css_string())
+        .as_deref(),
+        Ok(
+            "div{color:red;}@keyframes spin{from{opacity:0;}}@media print{@keyframes \
+             spin{to{opacity:1;}}}"
+        )
+    )
+}
+
+#[test]
+fn test_keyframes_invalid_selector() {
+    assert!(parse("@keyframes spin { div { opacity: 0; } }").is_err());
+}
+
 #[test]
 fn test_simple_mixin() {
     assert_matches!(
@@ -251,3 +313,72 @@ fn test_simple_mixin() {
         Ok("@mixin test{color:green;opacity:0;}div.open{color:red;@include test;}")
     )
 }
+
+#[test]
+fn test_namespace() {
+    let css = parse(
+        r"
+        @namespace url(http://www.w3.org/1999/xhtml);
+        @namespace svg url(http://www.w3.org/2000/svg);
+        svg|a {
+            *|rect, |circle {
+                color: red;
+            }
+
+            & || td {
+                color: blue;
+            }
+        }
+    ",
+    )
+    .unwrap()
+    .flatten_tree();
+
+    assert_eq!(css.namespace(""), Some("http://www.w3.org/1999/xhtml"));
+    assert_eq!(css.namespace("svg"), Some("http://www.w3.org/2000/svg"));
+    assert_eq!(css.namespace("math"), None);
+    assert_eq!(
+        css.as_css_string(),
+        "@namespace url(http://www.w3.org/1999/xhtml);@namespace svg \
+         url(http://www.w3.org/2000/svg);svg|a *|rect,svg|a |circle{color:red;}svg|a||td{color:\
+         blue;}"
+    );
+}
+
+#[test]
+fn test_charset() {
+    let mut css = parse("\u{feff}@charset \"utf-8\"; div { content: \"é\"; }")
+        .unwrap()
+        .flatten_tree();
+
+    procss::transformers::normalize_charset(&mut css);
+    assert_eq!(
+        css.as_css_string(),
+        "@charset \"UTF-8\";div{content:\"é\";}"
+    );
+
+    let mut css = parse("@charset \"utf-8\"; div { color: red; }")
+        .unwrap()
+        .flatten_tree();
+
+    procss::transformers::normalize_charset(&mut css);
+    assert_eq!(css.as_css_string(), "div{color:red;}");
+}
+
+#[test]
+fn test_page_pseudo() {
+    let tree = parse("@page :first { margin: 0; }").unwrap();
+    let Some(Ruleset::QualRuleset(ruleset)) = tree.iter().next() else {
+        panic!()
+    };
+
+    assert_matches!(
+        ruleset.0.at_rule(),
+        AtRule::Page(pages) if pages == [PageSelector { name: None, pseudo: vec!["first"] }]
+    );
+
+    assert_eq!(
+        tree.flatten_tree().as_css_string(),
+        "@page:first{margin:0;}"
+    );
+}
@@ -14,7 +14,7 @@
 #[cfg(test)]
 use std::assert_matches::assert_matches;
 
-use procss::{parse, RenderCss};
+use procss::{ast::Tree, parse, IntoOwned, RenderCss};
 
 #[test]
 fn test_css() {
@@ -101,3 +101,93 @@ fn test_selector_attribute_pseudo_correct_order() {
         Ok("div[data-value]:before{color:red;}")
     )
 }
+
+#[test]
+fn test_escaped_identifiers() {
+    let css = r"
+        .sm\:flex, .w-1\/2, .\31 23, #\#id {
+            color: red;
+            &:hover .md\:p-4 {
+                \63olor: blue;
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(css)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(concat!(
+            r".sm\:flex,.w-1\/2,.\31 23,#\#id{color:red;}",
+            r".sm\:flex:hover .md\:p-4,.w-1\/2:hover .md\:p-4,",
+            r".\31 23:hover .md\:p-4,#\#id:hover .md\:p-4{\63olor:blue;}"
+        ))
+    )
+}
+
+#[test]
+fn test_non_ascii_identifiers() {
+    assert_matches!(
+        parse(".größe > ✓ { --größe: 1px; }")
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(".größe>✓{--größe:1px;}")
+    )
+}
+
+#[test]
+fn test_custom_property_values() {
+    let css = "
+        div {
+            --empty: ;
+            --block: { a: b; c: [1;2] };
+            --spaced:  a   b  ;
+            --hover:hover { color: red };
+            --flag: x !important;
+            color: var(--block);
+        }
+    ";
+
+    assert_matches!(
+        parse(css)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(
+            "div{--empty:;--block:{ a: b; c: [1;2] };--spaced:a   b;--hover:hover { color: red \
+             };--flag:x!important;color:var(--block);}"
+        )
+    )
+}
+
+#[test]
+fn test_custom_property_verbatim() {
+    let css = "div { --f: foo(a;b) bar; --x: { a: b; }; --a: foo(  1px , 2px ) ; }";
+    assert_eq!(
+        parse(css).unwrap().flatten_tree().as_css_string(),
+        "div{--f:foo(a;b) bar;--x:{ a: b; };--a:foo(  1px , 2px );}"
+    );
+}
+
+#[test]
+fn test_custom_property_unbalanced() {
+    assert!(parse("div { --x: (]; }").is_err());
+}
+
+#[test]
+fn test_into_owned() {
+    fn parse_owned(input: String) -> Tree<'static> {
+        parse(&input).unwrap().into_owned()
+    }
+
+    let css = "div { .a, span:not(.b) > p { --x: { y: z }; color: red; } }";
+    let tree = parse_owned(css.to_owned());
+    assert_eq!(tree.as_css_string(), parse(css).unwrap().as_css_string());
+    let flat = std::thread::spawn(move || tree.flatten_tree().into_owned())
+        .join()
+        .unwrap();
+
+    assert_eq!(
+        flat.as_css_string(),
+        "div .a,div span:not(.b)>p{--x:{ y: z };color:red;}"
+    );
+}
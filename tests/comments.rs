@@ -0,0 +1,89 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+#![feature(assert_matches)]
+
+#[cfg(test)]
+use std::assert_matches::assert_matches;
+
+use procss::{
+    ast::{Comment, Ruleset},
+    parse, transformers, RenderCss,
+};
+
+static CSS: &str = "
+/*! License: Apache-2.0 */
+/* Not important */
+div {
+    /*! Keep me */
+    color: red;
+    /* Drop me */
+    .open {
+        color: green;
+    }
+}
+";
+
+#[test]
+fn test_important_comments() {
+    let css = parse(CSS).unwrap().flatten_tree().as_css_string();
+    assert_eq!(
+        css,
+        "/*! License: Apache-2.0 */div{/*! Keep me */color:red;}div .open{color:green;}"
+    );
+}
+
+#[test]
+fn test_comment_nodes() {
+    let tree = parse(CSS).unwrap();
+    assert_matches!(
+        tree.iter().next(),
+        Some(Ruleset::Comment(Comment {
+            text,
+            preserve: true,
+            ..
+        })) if text == "/*! License: Apache-2.0 */"
+    );
+}
+
+#[test]
+fn test_preserve_comments() {
+    let mut css = parse(CSS).unwrap().flatten_tree();
+    transformers::preserve_comments(&mut css);
+    assert_eq!(
+        css.as_css_string(),
+        "/*! License: Apache-2.0 *//* Not important */div{/*! Keep me */color:red;/* Drop me \
+         */}div .open{color:green;}"
+    );
+}
+
+#[test]
+fn test_at_rule_comments() {
+    let css = parse("@media screen { /*! Media */ div { color: red; } }")
+        .unwrap()
+        .flatten_tree()
+        .as_css_string();
+
+    assert_eq!(css, "@media screen{/*! Media */div{color:red;}}");
+}
+
+#[test]
+fn test_at_rule_mixed_comments() {
+    let css = parse("@media print { /*! a */ top: 0; /*! b */ div { c: d; } /*! e */ }")
+        .unwrap()
+        .flatten_tree()
+        .as_css_string();
+
+    assert_eq!(
+        css,
+        "@media print{/*! a */top:0;}@media print{/*! b */div{c:d;}/*! e */}"
+    );
+}
@@ -0,0 +1,195 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use procss::{
+    ast::{
+        Comment, QualRule, Qualifier, Rule, Ruleset, Selector, SelectorPath, SelectorTerm, Span,
+        Tree, TreeRule, TreeRuleset,
+    },
+    RenderCss,
+};
+
+#[test]
+fn test_selector_ruleset() {
+    let tree: Tree = [Ruleset::selector("div > .open, span")
+        .rule("color", "red")
+        .rule("font-size", "12px")]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        tree.flatten_tree().as_css_string(),
+        "div>.open,span{color:red;font-size:12px;}"
+    );
+}
+
+#[test]
+fn test_nested_ruleset() {
+    let tree: Tree = [Ruleset::selector("div")
+        .rule("color", "red")
+        .nest(Ruleset::selector("&:hover").rule("color", "blue"))
+        .nest(
+            Ruleset::at_rule("media", "(max-width: 600px)").nest(
+                Ruleset::selector(".a")
+                    .child(Comment {
+                        text: "/*! keep */".into(),
+                        preserve: true,
+                        span: Span::default(),
+                    })
+                    .child(Rule {
+                        important: true,
+                        ..Rule::new("display", "none")
+                    }),
+            ),
+        )]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        tree.flatten_tree().as_css_string(),
+        "div{color:red;}div:hover{color:blue;}@media(max-width:600px){div .a{/*! keep \
+         */display:none!important;}}"
+    );
+}
+
+#[test]
+fn test_at_rules() {
+    let tree: Tree = [
+        Ruleset::from(QualRule::new("import", "\"x.css\"")),
+        Ruleset::at_rule("font-face", "")
+            .rule("font-family", "\"My Font\"")
+            .into(),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        tree.as_css_string(),
+        "@import\"x.css\";@font-face{font-family:\"My Font\";}"
+    );
+}
+
+#[test]
+fn test_typed_selector() {
+    let term = SelectorTerm {
+        tag: Some("li".into()),
+        qualifiers: vec![Qualifier::Class("item".into())],
+        ..SelectorTerm::default()
+    };
+
+    let path = SelectorPath::Cons(term.clone(), vec![]);
+    let selector = Selector::new([path, SelectorPath::Cons(term, vec![])]).unwrap();
+    assert_eq!(selector.as_css_string(), "li.item,li.item");
+    assert!(Selector::new([]).is_none());
+
+    let tree: Tree = [Ruleset::selector(selector).rule("color", "red")]
+        .into_iter()
+        .collect();
+
+    assert_eq!(
+        tree.flatten_tree().as_css_string(),
+        "li.item,li.item{color:red;}"
+    );
+}
+
+#[test]
+fn test_selector_try_from() {
+    let selector = Selector::try_from(" a:not(.b) ").unwrap();
+    assert_eq!(selector.as_css_string(), "a:not(.b)");
+    assert_eq!(selector.span().start.offset, 0);
+
+    let err = Selector::try_from("a, {").unwrap_err();
+    let err = err.downcast::<procss::ParseError>().unwrap();
+    assert_eq!(err.message, "expected selector");
+    assert_eq!(err.position.column, 2);
+}
+
+#[test]
+#[should_panic]
+fn test_invalid_selector() {
+    let _: Tree = [Ruleset::selector("div {")].into_iter().collect();
+}
+
+#[test]
+fn test_try_selector() {
+    let ruleset = TreeRuleset::try_selector("div")
+        .unwrap()
+        .rule("color", "red");
+    assert_eq!(ruleset.as_css_string(), "div{color:red;}");
+
+    let err = TreeRuleset::try_selector("div {").unwrap_err();
+    let err = err.downcast::<procss::ParseError>().unwrap();
+    assert_eq!(err.message, "expected selector");
+}
+
+#[test]
+fn test_rule_important() {
+    let tree: Tree = [Ruleset::selector("div")
+        .rule("color", "red !important")
+        .rule("--x", "{ a: b }! IMPORTANT")]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        tree.flatten_tree().as_css_string(),
+        "div{color:red!important;--x:{ a: b }!important;}"
+    );
+}
+
+#[test]
+fn test_rule_invalid() {
+    assert!(Rule::try_new("color", "red; } body { x: y").is_err());
+    assert!(Rule::try_new("color", "red { x").is_err());
+    assert!(Rule::try_new("color: red", "blue").is_err());
+    assert!(Rule::try_new("--x", "{ a: b } }").is_err());
+    assert!(Rule::try_new("--x", "foo(a;b)").is_ok());
+    assert!(Rule::try_new("content", "\"; } { \"").is_ok());
+}
+
+#[test]
+fn test_at_rule_invalid() {
+    assert!(QualRule::try_new("media", "print{} body{color:red").is_err());
+    assert!(QualRule::try_new("media", "print;").is_err());
+    assert!(QualRule::try_new("media print", "").is_err());
+    assert!(QualRule::try_new("import", "\"a{b}.css\"").is_ok());
+
+    let ruleset = TreeRuleset::try_at_rule("media", "print")
+        .unwrap()
+        .rule("color", "red");
+    assert_eq!(ruleset.as_css_string(), "@media print{color:red;}");
+    assert!(TreeRuleset::try_at_rule("media", "print{} body{color:red").is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_at_rule_injection() {
+    let _ = Ruleset::<TreeRule>::at_rule("media", "print{} body{color:red");
+}
+
+#[test]
+#[should_panic]
+fn test_rule_injection() {
+    let _ = Ruleset::<TreeRule>::selector("div").rule("color", "red; } body { x: y");
+}
+
+#[test]
+fn test_invalid_suffix_dropped() {
+    let tree: Tree = [
+        Ruleset::selector(".a:hover").nest(Ruleset::selector("&__b").rule("color", "red")),
+        Ruleset::selector("[x]")
+            .rule("color", "green")
+            .nest(Ruleset::selector("&__b").rule("color", "blue")),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(tree.flatten_tree().as_css_string(), "[x]{color:green;}");
+}
@@ -0,0 +1,81 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+#![feature(assert_matches)]
+
+#[cfg(test)]
+use std::assert_matches::assert_matches;
+
+use procss::{
+    ast::{Cst, CstKind},
+    RenderCss,
+};
+
+static CSS: &str = "
+/* Header comment */
+@import \"test.css\" ;
+
+div   /* selector */ {
+    color: red;;
+    // line comment
+    .open {
+        content: \"}\" ;
+    }
+
+    @media screen and (max-width: 100px) {
+        color:blue
+    }
+}
+
+@font-face { font-family: \"My Font\"; }
+
+@keyframes spin {
+    /* start */
+    from { opacity: 0 }
+    12.5%, to {
+        opacity: 1;
+    }
+}
+";
+
+#[test]
+fn test_round_trip() {
+    let cst = Cst::parse(CSS).unwrap();
+    assert_eq!(cst.to_string(), CSS);
+}
+
+#[test]
+fn test_to_tree() {
+    let cst = Cst::parse(CSS).unwrap();
+    assert_eq!(
+        cst.to_tree().flatten_tree().as_css_string(),
+        procss::parse(CSS).unwrap().flatten_tree().as_css_string()
+    );
+}
+
+#[test]
+fn test_edit_preserves_trivia() {
+    let mut cst = Cst::parse("div {\n    /* brand */\n    color: red;\n}").unwrap();
+    let CstKind::Block(block) = &mut cst.children[0].kind else {
+        panic!();
+    };
+
+    assert_matches!(&block.children[0].kind, CstKind::Comment(_, text) if text == "/* brand */");
+    assert_matches!(&block.children[1].leading[..], "\n    ");
+    if let CstKind::Rule(_, text) = &mut block.children[1].kind {
+        *text = "color: green".into();
+    }
+
+    assert_eq!(
+        cst.to_string(),
+        "div {\n    /* brand */\n    color: green;\n}"
+    );
+}
@@ -128,6 +128,6 @@ fn test_advanced_mixin() {
                 flat.as_css_string()
             })
             .as_deref(),
-            Ok("div[theme=\"custom\"]{--button--font-size:16px;--config-button--padding:15px 8px 6px 8px;color:#161616;background-color:#f2f4f6;font-family:\"Open Sans\";--interface-monospace--font-family:\"Roboto Mono\";--button--font-family:\"theme Icons\";--group_by--content:\"Group By\";--split_by--content:\"Split By\";--inactive-column-selector--content:\"\\E835\";--active-column-selector--content:\"\\E834\";--chart-y1-label--content:\"arrow_upward\";--chart-y2-label--content:\"arrow_downward\";--chart-full--gradient:linear-gradient(#4d342f 0%,#e4521b 22.5%,#feeb65 42.5%,#f0f0f0 50%,#dcedc8 57.5%,#42b3d5 67.5%,#1a237e 100%);--chart-positive--gradient:linear-gradient(#f0f0f0 0%,#dcedc8 10%,#42b3d5 50%,#1a237e 100%);--chart-negative--gradient:linear-gradient(#4d342f 0%,#e4521b 50%,#feeb65 90%,#f0f0f0 100%);--column-style-open-button--content:\"menu\";--column-style-close-button--content:\"expand_less\";}div[theme=\"custom\"] table::-webkit-scrollbar-thumb{background-color:transparent;}div[theme=\"custom\"] table:hover::-webkit-scrollbar-thumb{background-color:#e0e4e9;}div[theme=\"custom\"]{--map-tile-url:\"http://{a-c}.basemaps.cartocdn.com/light_all/{z}/{x}/{y}.png\";}perspective-copy-menu[theme=\"custom\"],.perspective-modal-theme{font-family:\"Open Sans\";--interface-monospace--font-family:\"Roboto Mono\";--button--font-family:\"theme Icons\";color:#161616;background-color:#f2f4f6;background-color:white;--column-style-pos-color--content:\"add\";}")
+            Ok("div[theme=\"custom\"]{--button--font-size:16px;--config-button--padding:15px 8px 6px 8px;color:#161616;background-color:#f2f4f6;font-family:\"Open Sans\";--interface-monospace--font-family:\"Roboto Mono\";--button--font-family:\"theme Icons\";--group_by--content:\"Group By\";--split_by--content:\"Split By\";--inactive-column-selector--content:\"\\E835\";--active-column-selector--content:\"\\E834\";--chart-y1-label--content:\"arrow_upward\";--chart-y2-label--content:\"arrow_downward\";--chart-full--gradient:linear-gradient(\n                #4d342f 0%,\n                #e4521b 22.5%,\n                #feeb65 42.5%,\n                #f0f0f0 50%,\n                #dcedc8 57.5%,\n                #42b3d5 67.5%,\n                #1a237e 100%\n            );--chart-positive--gradient:linear-gradient(\n                #f0f0f0 0%,\n                #dcedc8 10%,\n                #42b3d5 50%,\n                #1a237e 100%\n            );--chart-negative--gradient:linear-gradient(\n                #4d342f 0%,\n                #e4521b 50%,\n                #feeb65 90%,\n        \n                #f0f0f0 100%\n            );--column-style-open-button--content:\"menu\";--column-style-close-button--content:\"expand_less\";}div[theme=\"custom\"] table::-webkit-scrollbar-thumb{background-color:transparent;}div[theme=\"custom\"] table:hover::-webkit-scrollbar-thumb{background-color:#e0e4e9;}div[theme=\"custom\"]{--map-tile-url:\"http://{a-c}.basemaps.cartocdn.com/light_all/{z}/{x}/{y}.png\";}perspective-copy-menu[theme=\"custom\"],.perspective-modal-theme{font-family:\"Open Sans\";--interface-monospace--font-family:\"Roboto Mono\";--button--font-family:\"theme Icons\";color:#161616;background-color:#f2f4f6;background-color:white;--column-style-pos-color--content:\"add\";}")
     )
 }
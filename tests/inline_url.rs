@@ -64,3 +64,27 @@ fn test_omit_non_path_inline_url() {
         Ok("div.open{color:url(\"test.svg\");}")
     )
 }
+
+#[test]
+fn test_inline_url_single_quotes() {
+    let ctx = procss::utils::fs::read_context();
+    ctx.expect()
+        .returning(|_| Ok("abcde".as_bytes().to_owned()));
+
+    assert_matches!(
+        parse(
+            "
+            div.open {
+                background: url('/test.png');
+            }
+        "
+        )
+        .map(|x| {
+            let mut css = x.flatten_tree();
+            inline_url("")(&mut css);
+            css.as_css_string()
+        })
+        .as_deref(),
+        Ok("div.open{background:url(\"data:image/png;base64,YWJjZGU=\");}")
+    )
+}
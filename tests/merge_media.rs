@@ -0,0 +1,145 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+#![feature(assert_matches)]
+
+#[cfg(test)]
+use std::assert_matches::assert_matches;
+
+use procss::{parse, transformers::merge_media, RenderCss};
+
+fn merged(input: &str) -> anyhow::Result<String> {
+    parse(input).map(|tree| {
+        let mut css = tree.flatten_tree();
+        merge_media(&mut css);
+        css.as_css_string()
+    })
+}
+
+#[test]
+fn test_merge_nested_media() {
+    assert_matches!(
+        merged(
+            "
+            @media (max-width: 1250px) {
+                @media (min-width: 50px) {
+                    div {
+                        color: red;
+                    }
+                }
+            }
+        "
+        )
+        .as_deref(),
+        Ok("@media(max-width:1250px)and (min-width:50px){div{color:red;}}")
+    )
+}
+
+#[test]
+fn test_merge_preserves_order() {
+    assert_matches!(
+        merged(
+            "
+            @media screen {
+                div {
+                    color: red;
+                }
+
+                @media (min-width: 50px) {
+                    div {
+                        color: green;
+                    }
+
+                    @media (orientation: landscape) {
+                        div {
+                            color: blue;
+                        }
+                    }
+                }
+
+                span {
+                    color: red;
+                }
+            }
+        "
+        )
+        .as_deref(),
+        Ok("@media screen{div{color:red;}}@media screen and \
+            (min-width:50px){div{color:green;}}@media screen and (min-width:50px)and \
+            (orientation:landscape){div{color:blue;}}@media screen{span{color:red;}}")
+    )
+}
+
+#[test]
+fn test_merge_media_list_product() {
+    assert_matches!(
+        merged(
+            "
+            @media screen, print {
+                @media (min-width: 50px), (orientation: portrait) {
+                    div {
+                        color: red;
+                    }
+                }
+            }
+        "
+        )
+        .as_deref(),
+        Ok(
+            "@media screen and (min-width:50px),screen and (orientation:portrait),print and \
+             (min-width:50px),print and (orientation:portrait){div{color:red;}}"
+        )
+    )
+}
+
+#[test]
+fn test_merge_media_drops_impossible() {
+    assert_matches!(
+        merged(
+            "
+            @media screen, (max-width: 100px) {
+                @media print, (min-width: 200px) {
+                    div {
+                        color: red;
+                    }
+                }
+
+                @media print and (min-width: 200px) {
+                    span {
+                        color: red;
+                    }
+                }
+            }
+        "
+        )
+        .as_deref(),
+        Ok("@media screen and (min-width:200px),print and (max-width:100px){div{color:red;}}")
+    )
+}
+
+#[test]
+fn test_merge_media_not_preserved() {
+    assert_matches!(
+        merged(
+            "
+            @media screen {
+                @media not print {
+                    div {
+                        color: red;
+                    }
+                }
+            }
+        "
+        )
+        .as_deref(),
+        Ok("@media screen{@media not print{div{color:red;}}}")
+    )
+}
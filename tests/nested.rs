@@ -14,7 +14,7 @@
 #[cfg(test)]
 use std::assert_matches::assert_matches;
 
-use procss::{parse, RenderCss};
+use procss::{parse, ParseError, RenderCss};
 
 #[test]
 fn test_nested() {
@@ -73,7 +73,7 @@ fn test_self() {
 }
 
 #[test]
-fn test_id_overrides_parent() {
+fn test_id_joins_parent() {
     let complex = "
         #test {
             &#test2:before {
@@ -86,7 +86,7 @@ fn test_id_overrides_parent() {
         parse(complex)
             .map(|x| x.flatten_tree().as_css_string())
             .as_deref(),
-        Ok("#test2:before{color:red;}")
+        Ok("#test#test2:before{color:red;}")
     )
 }
 
@@ -186,3 +186,315 @@ input {
         Ok("input:before{color:white;}input#my_id:before{color:red;}")
     )
 }
+
+#[test]
+fn test_self_in_pseudo_selector_list() {
+    let complex = "
+        div, span {
+            &:not(&.open, .closed) {
+                color: red;
+            }
+
+            :is(& > p) {
+                color: blue;
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(
+            "div:not(div.open,span.open,.closed),span:not(div.open,span.open,.closed){color:red;}:\
+             is(div>p,span>p){color:blue;}"
+        )
+    )
+}
+
+#[test]
+fn test_pseudo_element_stays_last() {
+    let complex = "
+        div {
+            &::before {
+                &:hover {
+                    color: red;
+                }
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok("div:hover::before{color:red;}")
+    )
+}
+
+#[test]
+fn test_self_in_any_position() {
+    let complex = "
+        .button {
+            .theme-dark & {
+                color: white;
+            }
+
+            & + & {
+                margin-left: 4px;
+            }
+
+            .toolbar > &.primary {
+                color: blue;
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(".theme-dark \
+            .button{color:white;}.button+.button{margin-left:4px;}.toolbar>.button.primary{color:\
+            blue;}")
+    )
+}
+
+#[test]
+fn test_self_in_selector_list_parent() {
+    let complex = "
+        div, span {
+            .theme-dark & {
+                color: white;
+            }
+
+            :not(&) {
+                color: red;
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(".theme-dark :is(div,span){color:white;}:not(div,span){color:red;}")
+    )
+}
+
+#[test]
+fn test_self_refers_to_resolved_parent() {
+    let complex = "
+        .a {
+            .b {
+                .c & {
+                    color: red;
+                }
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(".c :is(.a .b){color:red;}")
+    )
+}
+
+#[test]
+fn test_nest_rule() {
+    let complex = "
+        img {
+            @nest div & {
+                color: red;
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok("div img{color:red;}")
+    )
+}
+
+#[test]
+fn test_bem_suffix() {
+    let complex = "
+        .block {
+            &__elem {
+                color: red;
+            }
+
+            &--mod {
+                &.active {
+                    color: green;
+                }
+
+                &__elem {
+                    color: blue;
+                }
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(
+            ".block__elem{color:red;}.block--mod.active{color:green;}.block--mod__elem{color:blue;\
+             }"
+        )
+    )
+}
+
+#[test]
+fn test_suffix_tag_and_id() {
+    let complex = "
+        my-list, #main .list {
+            &-item {
+                color: red;
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok("my-list-item,#main .list-item{color:red;}")
+    )
+}
+
+#[test]
+fn test_suffix_last_simple_selector() {
+    let complex = "
+        .a#b {
+            &__c {
+                color: red;
+            }
+        }
+
+        .d:hover.e {
+            &__f {
+                color: green;
+            }
+        }
+
+        .g {
+            .x &__h {
+                color: blue;
+            }
+
+            &.i {
+                .y &__j {
+                    color: white;
+                }
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(
+            ".a#b__c{color:red;}.d:hover.e__f{color:green;}.x .g__h{color:blue;}.y \
+             .g.i__j{color:white;}"
+        )
+    )
+}
+
+#[test]
+fn test_suffix_invalid_parent() {
+    for complex in [
+        ".a > .b { & &__c { color: red; } }",
+        ".a .b { .x &__c { color: red; } }",
+        ".a, .b { .x &__c { color: red; } }",
+        ".a:hover { & { &__c { color: red; } } }",
+        ".a:hover { @media print { &__c { color: red; } } }",
+        ".a:hover { :not(&__c) { color: red; } }",
+        "[x] { &__c { color: red; } }",
+        "* { &__c { color: red; } }",
+    ] {
+        let err = parse(complex).unwrap_err();
+        let err = err.downcast::<ParseError>().unwrap();
+        assert_eq!(
+            err.message, "expected class, id or type selector before `&` suffix",
+            "{}",
+            complex
+        );
+    }
+}
+
+#[test]
+fn test_inner_nesting_rejected() {
+    for (complex, column) in [("div { .x& { a: b; } }", 9), ("#a { #b& { c: d; } }", 8)] {
+        let err = parse(complex).unwrap_err();
+        let err = err.downcast::<ParseError>().unwrap();
+        assert_eq!(err.message, "expected combinator before `&`", "{}", complex);
+        assert_eq!(err.position.column, column, "{}", complex);
+    }
+
+    assert_eq!(
+        parse("div { .x & { a: b; } }")
+            .unwrap()
+            .flatten_tree()
+            .as_css_string(),
+        ".x div{a:b;}"
+    );
+}
+
+#[test]
+fn test_bubble_media() {
+    let complex = "
+        div {
+            color: blue;
+            @media (max-width: 600px) {
+                color: red;
+                span {
+                    color: green;
+                }
+
+                @supports (display: grid) {
+                    display: grid;
+                }
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(
+            "div{color:blue;}@media(max-width:600px){div{color:red;}div \
+             span{color:green;}@supports(display:grid){div{display:grid;}}}"
+        )
+    )
+}
+
+#[test]
+fn test_qualifiers_keep_source_order() {
+    let complex = "
+        .x#a {
+            color: red;
+        }
+
+        #a.x {
+            &.y#b[c]:hover {
+                color: blue;
+            }
+        }
+    ";
+
+    assert_matches!(
+        parse(complex)
+            .map(|x| x.flatten_tree().as_css_string())
+            .as_deref(),
+        Ok(".x#a{color:red;}#a.x.y#b[c]:hover{color:blue;}")
+    )
+}
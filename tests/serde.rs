@@ -0,0 +1,69 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+#![cfg(feature = "serde")]
+
+use procss::{ast, parse, RenderCss};
+use serde_json::json;
+
+#[test]
+fn test_serialize_rule() {
+    let tree = parse("div{color:red !important}").unwrap();
+    let json = serde_json::to_value(&tree).unwrap();
+    let rule = &json[0]["SelectorRuleset"][1][0]["Rule"];
+    assert_eq!(rule["property"], json!("color"));
+    assert_eq!(rule["value"], json!("red"));
+    assert_eq!(rule["important"], json!(true));
+    assert_eq!(
+        rule["span"]["start"],
+        json!({"offset": 4, "line": 1, "column": 5})
+    );
+}
+
+#[test]
+fn test_serialize_selector() {
+    let tree = parse("div > .a, p{}").unwrap();
+    let json = serde_json::to_value(&tree).unwrap();
+    let selector = &json[0]["SelectorRuleset"][0];
+    assert_eq!(selector.as_array().unwrap().len(), 2);
+    assert_eq!(selector[0]["Cons"][0]["tag"], json!("div"));
+    assert_eq!(selector[0]["Cons"][1][0][0], json!("Desc"));
+    assert_eq!(
+        selector[0]["Cons"][1][0][1]["qualifiers"],
+        json!([{ "Class": "a" }])
+    );
+    assert_eq!(selector[1]["Cons"][0]["tag"], json!("p"));
+}
+
+#[test]
+fn test_round_trip() {
+    let css = "
+        @import \"x.css\";
+        @media (max-width: 100px) { div { &:hover, .a[href^=\"http\" i] { --x: { y: z }; } } }
+        @keyframes fade { from { opacity: 0 } 50% { opacity: 1 } }
+        /*! license */
+    ";
+
+    let tree = parse(css).unwrap();
+    let json = serde_json::to_string(&tree).unwrap();
+    let tree2: ast::Tree = serde_json::from_str(&json).unwrap();
+    assert_eq!(tree2.as_css_string(), tree.as_css_string());
+    assert_eq!(
+        tree2.flatten_tree().as_css_string(),
+        tree.flatten_tree().as_css_string()
+    );
+}
+
+#[test]
+fn test_deserialize_empty_selector() {
+    let json = r#"[{"SelectorRuleset": [[], [], {"start": {"offset": 0, "line": 1, "column": 1}, "end": {"offset": 0, "line": 1, "column": 1}}]}]"#;
+    assert!(serde_json::from_str::<ast::Tree>(json).is_err());
+}
@@ -0,0 +1,82 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+#![feature(assert_matches)]
+
+#[cfg(test)]
+use std::assert_matches::assert_matches;
+
+use procss::{
+    ast::{Position, Rule, Ruleset, Span},
+    parse,
+};
+
+static CSS: &str = "div {
+    color: red;
+    .open {
+        color: green;
+    }
+}";
+
+#[test]
+fn test_rule_span() {
+    let mut spans = vec![];
+    parse(CSS)
+        .unwrap()
+        .transform(|rule: &mut Rule| spans.push(rule.span));
+    assert_matches!(spans[..], [
+        Span {
+            start: Position {
+                line: 2,
+                column: 5,
+                ..
+            },
+            ..
+        },
+        Span {
+            start: Position {
+                line: 4,
+                column: 9,
+                ..
+            },
+            ..
+        }
+    ]);
+
+    assert_eq!(spans[0].as_str(CSS), Some("color: red"));
+}
+
+#[test]
+fn test_flat_ruleset_span() {
+    let css = parse(CSS).unwrap().flatten_tree();
+    let spans = css.iter().map(Ruleset::span).collect::<Vec<_>>();
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[0].start.line, 1);
+    assert_eq!(spans[0].end.line, 6);
+    assert_eq!(spans[1].start.line, 3);
+    assert_eq!(
+        spans[1].as_str(CSS).map(|x| x.starts_with(".open")),
+        Some(true)
+    );
+}
+
+#[test]
+fn test_selector_span() {
+    let css = parse(CSS).unwrap().flatten_tree();
+    let Some(Ruleset::SelectorRuleset(ruleset)) = css.iter().nth(1) else {
+        panic!()
+    };
+
+    assert_eq!(
+        ruleset.0.span().as_str(CSS),
+        Some("div {\n    color: red;\n    .open")
+    );
+}
@@ -0,0 +1,243 @@
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │  ██████╗ ██████╗  ██████╗   Copyright (C) 2022, The Prospective Company   │
+// │  ██╔══██╗██╔══██╗██╔═══██╗                                                │
+// │  ██████╔╝██████╔╝██║   ██║  This file is part of the Procss library,      │
+// │  ██╔═══╝ ██╔══██╗██║   ██║  distributed under the terms of the            │
+// │  ██║     ██║  ██║╚██████╔╝  Apache License 2.0.  The full license can     │
+// │  ╚═╝     ╚═╝  ╚═╝ ╚═════╝   be found in the LICENSE file.                 │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+
+use std::ops::ControlFlow;
+
+use procss::{
+    ast::{QualRule, Qualifier, Rule, Ruleset, Selector, SelectorAttr, SelectorTerm},
+    parse,
+    visit::{self, Ancestor, Context, Visit, VisitMut, Walk},
+    RenderCss,
+};
+
+const CSS: &str = "
+    @import \"x.css\";
+    div, span {
+        color: red;
+        &:not(.a, [href]) > p:has(> img) { top: 0; }
+        @media print {
+            .b:nth-child(2n of li.c) { color: blue; }
+        }
+    }
+    @keyframes spin { 50% { opacity: 0; } }
+";
+
+/// Records the nodes visited, along with the ancestors of declarations.
+#[derive(Default)]
+struct Log(Vec<String>);
+
+impl<'ast> Visit<'ast> for Log {
+    fn visit_rule(&mut self, rule: &'ast Rule<'ast>, cx: &mut Context<'ast>) -> ControlFlow<()> {
+        let ancestors = cx
+            .ancestors()
+            .iter()
+            .map(|x| match x {
+                Ancestor::Selector(x) => x.as_css_string(),
+                Ancestor::AtRule(x) => format!("@{}", x.0),
+                Ancestor::Keyframe(x) => x.iter().map(|x| x.as_css_string()).collect(),
+                Ancestor::Pseudo(x) => x.as_css_string(),
+            })
+            .collect::<Vec<_>>();
+
+        self.0
+            .push(format!("{} in {}", rule.property, ancestors.join(" ")));
+
+        ControlFlow::Continue(())
+    }
+
+    fn visit_qual_rule(
+        &mut self,
+        rule: &'ast QualRule<'ast>,
+        _cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        self.0.push(format!("@{}", rule.0));
+        ControlFlow::Continue(())
+    }
+
+    fn visit_selector_attr(
+        &mut self,
+        attr: &'ast SelectorAttr<'ast>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        let pseudo = cx
+            .ancestors()
+            .iter()
+            .any(|x| matches!(x, Ancestor::Pseudo(_)));
+        self.0.push(format!("[{}] in pseudo {}", attr.name, pseudo));
+        ControlFlow::Continue(())
+    }
+
+    fn visit_selector_term<T>(
+        &mut self,
+        term: &'ast SelectorTerm<'ast, T>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        let classes = term.classes().collect::<Vec<_>>().join(".");
+        self.0.push(format!("enter {}", classes));
+        visit::walk_selector_term(self, term, cx)?;
+        self.0.push(format!("exit {}", classes));
+        ControlFlow::Continue(())
+    }
+}
+
+#[test]
+fn test_visit_ancestors() {
+    let tree = parse(CSS).unwrap();
+    let mut log = Log::default();
+    tree.visit(&mut log);
+    let rules = log
+        .0
+        .iter()
+        .filter(|x| x.contains(" in ") && !x.contains("pseudo"))
+        .collect::<Vec<_>>();
+
+    assert_eq!(rules, vec![
+        "color in div,span",
+        "top in div,span &:not(.a,[href])>p:has(>img)",
+        "color in div,span @media .b:nth-child(2n of li.c)",
+        "opacity in @keyframes 50%",
+    ]);
+}
+
+#[test]
+fn test_visit_every_node() {
+    let tree = parse(CSS).unwrap();
+    let mut log = Log::default();
+    tree.visit(&mut log);
+    assert_eq!(log.0[0], "@import");
+    assert!(log.0.contains(&"@media".to_owned()));
+    assert!(log.0.contains(&"@keyframes".to_owned()));
+    assert!(log.0.contains(&"[href] in pseudo true".to_owned()));
+    for class in ["a", "b", "c"] {
+        assert!(log.0.contains(&format!("enter {}", class)));
+        assert!(log.0.contains(&format!("exit {}", class)));
+    }
+
+    // Terms in a pseudo-class argument are visited between the enter and exit
+    // of the term the pseudo-class belongs to.
+    let enter = log.0.iter().position(|x| x == "enter b").unwrap();
+    let inner = log.0.iter().position(|x| x == "enter c").unwrap();
+    let exit = log.0.iter().position(|x| x == "exit b").unwrap();
+    assert!(enter < inner && inner < exit);
+}
+
+#[test]
+fn test_visit_flat_css() {
+    let css = parse(CSS).unwrap().flatten_tree();
+    let mut log = Log::default();
+    css.visit(&mut log);
+    assert!(log.0.contains(
+        &"color in @media div .b:nth-child(2n of li.c),span .b:nth-child(2n of li.c)".to_owned()
+    ));
+}
+
+/// Finds the selector of the first `color` declaration, then stops.
+#[derive(Default)]
+struct FindColor(Vec<String>);
+
+impl<'ast> Visit<'ast> for FindColor {
+    fn visit_rule(&mut self, rule: &'ast Rule<'ast>, cx: &mut Context<'ast>) -> ControlFlow<()> {
+        self.0.push(rule.property.to_string());
+        if rule.property == "color" {
+            self.0.push(cx.selector().unwrap().as_css_string());
+            return ControlFlow::Break(());
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+#[test]
+fn test_visit_stop() {
+    let tree = parse("a { top: 0; b { color: red; } i { color: blue; } }").unwrap();
+    let mut find = FindColor::default();
+    let flow = find.visit_tree(&tree, &mut Context::default());
+    assert_eq!(flow, ControlFlow::Break(()));
+    assert_eq!(find.0, vec!["top", "color", "b"]);
+}
+
+/// Counts the declarations outside of `@media` blocks, by skipping them.
+#[derive(Default)]
+struct CountRules(usize);
+
+impl<'ast> Visit<'ast> for CountRules {
+    fn visit_rule(&mut self, _rule: &'ast Rule<'ast>, _cx: &mut Context<'ast>) -> ControlFlow<()> {
+        self.0 += 1;
+        ControlFlow::Continue(())
+    }
+
+    fn visit_ruleset<T: Walk<'ast>>(
+        &mut self,
+        ruleset: &'ast Ruleset<'ast, T>,
+        cx: &mut Context<'ast>,
+    ) -> ControlFlow<()> {
+        match ruleset {
+            Ruleset::QualRuleset(x) if x.0 .0 == "media" => ControlFlow::Continue(()),
+            _ => visit::walk_ruleset(self, ruleset, cx),
+        }
+    }
+}
+
+#[test]
+fn test_visit_skip() {
+    let tree = parse(CSS).unwrap();
+    let mut count = CountRules::default();
+    tree.visit(&mut count);
+    assert_eq!(count.0, 3);
+}
+
+/// Prefixes every class name, and upper-cases the properties of rulesets
+/// within `@media`.
+struct Prefix;
+
+impl<'a> VisitMut<'a> for Prefix {
+    fn visit_selector_term_mut<T>(
+        &mut self,
+        term: &mut SelectorTerm<'a, T>,
+        cx: &mut Context<'a>,
+    ) -> ControlFlow<()> {
+        for qualifier in term.qualifiers.iter_mut() {
+            if let Qualifier::Class(class) = qualifier {
+                *class = format!("x-{}", class).into();
+            }
+        }
+
+        visit::walk_selector_term_mut(self, term, cx)
+    }
+
+    fn visit_rule_mut(&mut self, rule: &mut Rule<'a>, cx: &mut Context<'a>) -> ControlFlow<()> {
+        if cx.at_rule("MEDIA").is_some() {
+            rule.property = rule.property.to_uppercase().into();
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+#[test]
+fn test_visit_mut() {
+    let mut tree = parse(CSS).unwrap();
+    tree.visit_mut(&mut Prefix);
+    assert_eq!(
+        tree.flatten_tree().as_css_string(),
+        "@import\"x.css\";div,span{color:red;}div:not(.x-a,[href])>p:has(>img),span:not(.x-a,\
+         [href])>p:has(>img){top:0;}@media print{div .x-b:nth-child(2n of li.x-c),span \
+         .x-b:nth-child(2n of li.x-c){COLOR:blue;}}@keyframes spin{50%{opacity:0;}}"
+    );
+}
+
+#[test]
+fn test_visit_node() {
+    let selector = Selector::try_from(".a > .b").unwrap();
+    let mut log = Log::default();
+    let _ = log.visit_selector(&selector, &mut Context::default());
+    assert_eq!(log.0, vec!["enter a", "exit a", "enter b", "exit b"]);
+}